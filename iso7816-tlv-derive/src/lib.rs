@@ -0,0 +1,235 @@
+//! Derive macro for typed BER-TLV data objects.
+//!
+//! This crate is not meant to be used directly,
+//! see `iso7816_tlv::ber::BerTlv` with the `derive` feature of the `iso7816-tlv` crate.
+
+#![deny(missing_docs)]
+#![deny(clippy::all)]
+#![deny(clippy::pedantic)]
+
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Fields, Ident, LitInt};
+
+/// Implements `ber::Template` for a struct with named fields, each field matching a child by tag.
+///
+/// Field attributes:
+/// - `#[tlv(tag = 0x5F20)]`: primitive child, converted with `TryFrom<&[u8]>` and `Into<Vec<u8>>`;
+/// - `#[tlv(constructed = 0xA5)]`: constructed child, itself a `ber::Template`;
+/// - `#[tlv(optional)]`: the field is an `Option`, `None` if the child is missing;
+/// - `#[tlv(repeated)]`: the field is a `Vec`, holding all children with the tag.
+///
+/// With a `#[tlv(tag = 0x6F)]` attribute on the struct,
+/// also implements `TryFrom<&ber::Tlv>` and `From<_> for ber::Tlv`.
+#[proc_macro_derive(BerTlv, attributes(tlv))]
+pub fn derive_ber_tlv(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// How many children a field matches.
+enum Occurs {
+    Once,
+    Optional,
+    Repeated,
+}
+
+struct Field {
+    ident: Ident,
+    tag: u64,
+    constructed: bool,
+    occurs: Occurs,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "BerTlv can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "BerTlv can only be derived for structs",
+            ))
+        }
+    };
+    let fields = fields
+        .iter()
+        .map(|f| {
+            let ident = f
+                .ident
+                .clone()
+                .ok_or_else(|| syn::Error::new_spanned(f, "unnamed field"))?;
+            parse_field(ident, &f.attrs)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let struct_tag = parse_struct_tag(&input.attrs)?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let krate = quote!(::iso7816_tlv::ber);
+
+    let decode = fields.iter().map(decode_field);
+    let encode = fields.iter().map(encode_field);
+
+    let mut expanded = quote! {
+        impl #impl_generics #krate::Template for #name #ty_generics #where_clause {
+            fn from_children(
+                children: &[#krate::Tlv],
+            ) -> ::core::result::Result<Self, ::iso7816_tlv::TlvError> {
+                use #krate::__private as __tlv;
+                let mut __decoder = __tlv::Decoder::new(children);
+                let __value = Self {
+                    #(#decode)*
+                };
+                __decoder.finish()?;
+                ::core::result::Result::Ok(__value)
+            }
+
+            fn into_children(self) -> #krate::__private::Vec<#krate::Tlv> {
+                use #krate::__private as __tlv;
+                let mut __encoder = __tlv::Encoder::default();
+                #(#encode)*
+                __encoder.finish()
+            }
+        }
+    };
+
+    if let Some(tag) = struct_tag {
+        let tag = tag_const(tag, true);
+        expanded.extend(quote! {
+            impl #impl_generics ::core::convert::TryFrom<&#krate::Tlv> for #name #ty_generics #where_clause {
+                type Error = ::iso7816_tlv::TlvError;
+
+                fn try_from(tlv: &#krate::Tlv) -> ::core::result::Result<Self, Self::Error> {
+                    #krate::__private::from_tlv(tlv, &#tag)
+                }
+            }
+
+            impl #impl_generics ::core::convert::From<#name #ty_generics> for #krate::Tlv #where_clause {
+                fn from(value: #name #ty_generics) -> Self {
+                    #krate::__private::into_tlv(value, &#tag)
+                }
+            }
+        });
+    }
+    Ok(expanded)
+}
+
+// `field: value,` in the struct expression of `from_children()`
+fn decode_field(f: &Field) -> TokenStream2 {
+    let ident = &f.ident;
+    let tag = tag_const(f.tag, f.constructed);
+    let (single, multiple) = if f.constructed {
+        (quote!(constructed), quote!(constructeds))
+    } else {
+        (quote!(primitive), quote!(primitives))
+    };
+    let value = match f.occurs {
+        Occurs::Once => {
+            quote!(__tlv::required(__decoder.#single(&#tag)?, &#tag)?)
+        }
+        Occurs::Optional => quote!(__decoder.#single(&#tag)?),
+        Occurs::Repeated => quote!(__decoder.#multiple(&#tag)?),
+    };
+    quote!(#ident: #value,)
+}
+
+// statements adding the field to the children in `into_children()`
+fn encode_field(f: &Field) -> TokenStream2 {
+    let ident = &f.ident;
+    let tag = tag_const(f.tag, f.constructed);
+    let method = if f.constructed {
+        quote!(constructed)
+    } else {
+        quote!(primitive)
+    };
+    match f.occurs {
+        Occurs::Once => quote!(__encoder.#method(&#tag, self.#ident);),
+        Occurs::Optional => quote! {
+            if let ::core::option::Option::Some(__value) = self.#ident {
+                __encoder.#method(&#tag, __value);
+            }
+        },
+        Occurs::Repeated => quote! {
+            for __value in self.#ident {
+                __encoder.#method(&#tag, __value);
+            }
+        },
+    }
+}
+
+// tag checked at compile time, including its consistency with the value
+fn tag_const(tag: u64, constructed: bool) -> TokenStream2 {
+    let tag = Literal::u64_suffixed(tag);
+    quote! {{
+        const TAG: ::iso7816_tlv::ber::Tag = ::iso7816_tlv::ber::Tag::__from_literal(#tag, #constructed);
+        TAG
+    }}
+}
+
+fn parse_struct_tag(attrs: &[Attribute]) -> syn::Result<Option<u64>> {
+    let mut tag = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("tlv")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tag") {
+                tag = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `tag = ...`"))
+            }
+        })?;
+    }
+    Ok(tag)
+}
+
+fn parse_field(ident: Ident, attrs: &[Attribute]) -> syn::Result<Field> {
+    let mut tag = None;
+    let mut occurs = Occurs::Once;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("tlv")) {
+        attr.parse_nested_meta(|meta| {
+            let constructed = meta.path.is_ident("constructed");
+            if meta.path.is_ident("tag") || constructed {
+                if tag.is_some() {
+                    return Err(meta.error("duplicate tag"));
+                }
+                let value = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+                tag = Some((value, constructed));
+            } else if meta.path.is_ident("optional") || meta.path.is_ident("repeated") {
+                if !matches!(occurs, Occurs::Once) {
+                    return Err(meta.error("`optional` and `repeated` are mutually exclusive"));
+                }
+                occurs = if meta.path.is_ident("optional") {
+                    Occurs::Optional
+                } else {
+                    Occurs::Repeated
+                };
+            } else {
+                return Err(meta
+                    .error("expected `tag = ...`, `constructed = ...`, `optional` or `repeated`"));
+            }
+            Ok(())
+        })?;
+    }
+    match tag {
+        Some((tag, constructed)) => Ok(Field {
+            ident,
+            tag,
+            constructed,
+            occurs,
+        }),
+        None => Err(syn::Error::new_spanned(
+            &ident,
+            "missing `#[tlv(tag = ...)]` or `#[tlv(constructed = ...)]` attribute",
+        )),
+    }
+}
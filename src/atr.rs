@@ -0,0 +1,554 @@
+//! This module provides tools and utilities for handling Answer-to-Reset (ATR)
+//! as defined in [ISO7816-3][iso7816-3] and [ISO7816-4][iso7816-4].
+//!
+//! Historical bytes are decoded into [COMPACT-TLV](crate::compact) data objects
+//! when their category indicator allows it (with the `alloc` feature).
+//!
+//! [iso7816-3]: https://www.iso.org/standard/38770.html
+//! [iso7816-4]: https://www.iso.org/standard/54550.html
+//!
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt;
+
+use untrusted::{Input, Reader};
+
+#[cfg(feature = "alloc")]
+use crate::compact;
+use crate::{ErrorKind, Result};
+
+/// Convention used for character transmission, given by the initial character TS.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Convention {
+    /// Direct convention (TS = '3B')
+    Direct,
+    /// Inverse convention (TS = '3F')
+    Inverse,
+}
+
+/// Interface bytes `TAi`, `TBi`, `TCi` and `TDi` of an ATR.
+/// Absent bytes are `None`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct InterfaceBytes {
+    ta: Option<u8>,
+    tb: Option<u8>,
+    tc: Option<u8>,
+    td: Option<u8>,
+}
+
+impl InterfaceBytes {
+    /// Get `TAi` interface byte
+    #[must_use]
+    pub fn ta(&self) -> Option<u8> {
+        self.ta
+    }
+
+    /// Get `TBi` interface byte
+    #[must_use]
+    pub fn tb(&self) -> Option<u8> {
+        self.tb
+    }
+
+    /// Get `TCi` interface byte
+    #[must_use]
+    pub fn tc(&self) -> Option<u8> {
+        self.tc
+    }
+
+    /// Get `TDi` interface byte
+    #[must_use]
+    pub fn td(&self) -> Option<u8> {
+        self.td
+    }
+
+    /// Protocol type T indicated by `TDi`, if present
+    #[must_use]
+    pub fn protocol(&self) -> Option<u8> {
+        self.td.map(|td| td & 0x0F)
+    }
+
+    // reads the interface bytes indicated by `y`, the high nibble of T0 or TDi-1
+    fn read(r: &mut Reader, y: u8) -> Result<Self> {
+        let mut read_if = |mask: u8| -> Result<Option<u8>> {
+            if y & mask == 0 {
+                Ok(None)
+            } else {
+                Ok(Some(r.read_byte()?))
+            }
+        };
+        Ok(Self {
+            ta: read_if(0x1)?,
+            tb: read_if(0x2)?,
+            tc: read_if(0x4)?,
+            td: read_if(0x8)?,
+        })
+    }
+}
+
+/// Decoded historical bytes of an ATR, depending on their category indicator.
+#[cfg(feature = "alloc")]
+#[derive(PartialEq, Debug, Clone)]
+pub enum HistoricalBytes {
+    /// No historical bytes
+    Empty,
+    /// Category indicator '00': COMPACT-TLV data objects followed by
+    /// a mandatory status indicator (LCS, SW1, SW2)
+    StatusIndicatorLast(Vec<compact::Tlv>, [u8; 3]),
+    /// Category indicator '80': COMPACT-TLV data objects,
+    /// possibly including a status indicator
+    CompactTlv(Vec<compact::Tlv>),
+    /// Category indicator '10': DIR data reference
+    DirDataReference(u8),
+    /// RFU or proprietary category indicator, raw historical bytes
+    /// (including the category indicator)
+    Proprietary(Vec<u8>),
+}
+
+#[cfg(feature = "alloc")]
+impl HistoricalBytes {
+    fn parse_compact(input: &[u8]) -> Result<Vec<compact::Tlv>> {
+        let mut ret = Vec::new();
+        let mut buf = input;
+        while !buf.is_empty() {
+            let (r, remaining) = compact::Tlv::parse(buf);
+            ret.push(r?);
+            buf = remaining;
+        }
+        Ok(ret)
+    }
+
+    /// Decodes historical bytes.
+    /// # Errors
+    /// Fails with [`ErrorKind::InvalidInput`] or [`ErrorKind::TruncatedInput`] when the historical bytes
+    /// do not match the format given by their category indicator.
+    pub fn parse(input: &[u8]) -> Result<Self> {
+        match input {
+            [] => Ok(Self::Empty),
+            [0x00, objects @ .., lcs, sw1, sw2] => Ok(Self::StatusIndicatorLast(
+                Self::parse_compact(objects)?,
+                [*lcs, *sw1, *sw2],
+            )),
+            [0x00, ..] => Err(ErrorKind::TruncatedInput.into()),
+            [0x80, objects @ ..] => Ok(Self::CompactTlv(Self::parse_compact(objects)?)),
+            [0x10, reference] => Ok(Self::DirDataReference(*reference)),
+            [0x10, ..] => Err(ErrorKind::InvalidInput.into()),
+            _ => Ok(Self::Proprietary(input.to_vec())),
+        }
+    }
+}
+
+/// Answer-to-Reset, as defined in [ISO7816-3].
+/// > The answer-to-reset consists of the initial character TS followed by
+/// > at most 32 characters in the following order:
+/// > - the format byte T0 (mandatory);
+/// > - the interface bytes `TAi` `TBi` `TCi` `TDi` (optional);
+/// > - the historical bytes T1, T2, ..., TK (optional);
+/// > - the check byte TCK (conditional).
+///
+/// # Example
+/// ```rust
+/// use iso7816_tlv::atr::{Atr, Convention};
+/// # use iso7816_tlv::TlvError;
+///
+/// # fn main() -> Result<(), TlvError> {
+/// let raw = [
+///     0x3B, 0x8A, 0x01, 0x00, 0x31, 0xC0, 0x73, 0xC8, 0x21, 0x10, 0x00, 0x90, 0x00, 0x60,
+/// ];
+/// let atr = Atr::from_bytes(&raw)?;
+/// assert_eq!(Convention::Direct, atr.convention());
+/// assert_eq!(Some(1), atr.interface_bytes()[0].protocol());
+/// assert_eq!(Some(0x60), atr.tck());
+/// println!("{}", atr);
+///
+/// // with the `alloc` feature
+/// # #[cfg(feature = "alloc")]
+/// # {
+/// use iso7816_tlv::atr::HistoricalBytes;
+///
+/// assert_eq!(vec![1], atr.protocols());
+/// if let HistoricalBytes::StatusIndicatorLast(objects, status) = atr.historical()? {
+///     assert_eq!(2, objects.len());
+///     assert_eq!([0x00, 0x90, 0x00], status);
+/// }
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(PartialEq, Clone)]
+pub struct Atr {
+    convention: Convention,
+    t0: u8,
+    // stored inline, so that ATRs are decoded without allocation
+    interface: [InterfaceBytes; Self::MAX_INTERFACE],
+    interface_len: usize,
+    // the number of historical bytes K is given by T0
+    historical: [u8; 15],
+    tck: Option<u8>,
+}
+
+impl Atr {
+    // each set of interface bytes takes at least one of the 32 characters following TS
+    const MAX_INTERFACE: usize = 32;
+
+    /// Get the convention given by TS
+    #[must_use]
+    pub fn convention(&self) -> Convention {
+        self.convention
+    }
+
+    /// Get the format byte T0
+    #[must_use]
+    pub fn t0(&self) -> u8 {
+        self.t0
+    }
+
+    /// Get interface bytes.
+    /// The first element holds TA1, TB1, TC1 and TD1, and so on.
+    #[must_use]
+    pub fn interface_bytes(&self) -> &[InterfaceBytes] {
+        &self.interface[..self.interface_len]
+    }
+
+    /// Get the protocol types indicated in the ATR, in order of appearance.
+    /// If no protocol is indicated, T=0 is returned.
+    #[cfg(feature = "alloc")]
+    #[must_use]
+    pub fn protocols(&self) -> Vec<u8> {
+        let ret: Vec<u8> = self
+            .interface_bytes()
+            .iter()
+            .filter_map(InterfaceBytes::protocol)
+            .collect();
+        if ret.is_empty() {
+            vec![0]
+        } else {
+            ret
+        }
+    }
+
+    /// Get raw historical bytes
+    #[must_use]
+    pub fn historical_bytes(&self) -> &[u8] {
+        &self.historical[..usize::from(self.t0 & 0x0F)]
+    }
+
+    /// Get decoded historical bytes.
+    /// # Errors
+    /// see [`HistoricalBytes::parse()`]
+    #[cfg(feature = "alloc")]
+    pub fn historical(&self) -> Result<HistoricalBytes> {
+        HistoricalBytes::parse(self.historical_bytes())
+    }
+
+    /// Get the check byte TCK, if present
+    #[must_use]
+    pub fn tck(&self) -> Option<u8> {
+        self.tck
+    }
+
+    fn read(r: &mut Reader) -> Result<Self> {
+        let convention = match r.read_byte()? {
+            0x3B => Convention::Direct,
+            0x3F => Convention::Inverse,
+            _ => return Err(ErrorKind::InvalidInput.into()),
+        };
+        let mut interface = [InterfaceBytes::default(); Self::MAX_INTERFACE];
+        let mut interface_len = 0;
+        let (checked, (t0, historical, tck)) = r.read_partial(|r| -> Result<_> {
+            let t0 = r.read_byte()?;
+            // TCK is absent if only T=0 is indicated
+            let mut tck_present = false;
+            let mut y = t0 >> 4;
+            while y != 0 {
+                let bytes = InterfaceBytes::read(r, y)?;
+                tck_present |= bytes.protocol().is_some_and(|t| t != 0);
+                y = bytes.td.map_or(0, |td| td >> 4);
+                *interface
+                    .get_mut(interface_len)
+                    .ok_or(ErrorKind::InvalidLength)? = bytes;
+                interface_len += 1;
+            }
+            let historical = r.read_bytes(usize::from(t0 & 0x0F))?;
+            let tck = if tck_present {
+                Some(r.read_byte()?)
+            } else {
+                None
+            };
+            Ok((t0, historical, tck))
+        })?;
+
+        // at most 32 characters follow TS
+        if checked.len() > 32 {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+        // exclusive-or of all bytes from T0 to TCK shall be null
+        if tck.is_some()
+            && checked
+                .as_slice_less_safe()
+                .iter()
+                .fold(0, |acc, x| acc ^ x)
+                != 0
+        {
+            return Err(ErrorKind::InvalidChecksum.into());
+        }
+
+        let mut historical_bytes = [0; 15];
+        historical_bytes[..historical.len()].copy_from_slice(historical.as_slice_less_safe());
+        Ok(Self {
+            convention,
+            t0,
+            interface,
+            interface_len,
+            historical: historical_bytes,
+            tck,
+        })
+    }
+
+    /// Parses a byte array into an ATR.
+    /// This also returns the unprocessed data.
+    pub fn parse(input: &[u8]) -> (Result<Self>, &[u8]) {
+        let mut r = Reader::new(Input::from(input));
+        (
+            Self::read(&mut r),
+            r.read_bytes_to_end().as_slice_less_safe(),
+        )
+    }
+
+    /// Parses a byte array into an ATR.
+    /// Input must exactly match an ATR.
+    /// # Errors
+    /// Fails with [`ErrorKind::InvalidInput`] if input does not match an ATR,
+    /// with [`ErrorKind::InvalidLength`] if more than 32 characters follow TS,
+    /// or with [`ErrorKind::InvalidChecksum`] if TCK is wrong.
+    pub fn from_bytes(input: &[u8]) -> Result<Self> {
+        let (r, n) = Self::parse(input);
+        if n.is_empty() {
+            r
+        } else {
+            Err(ErrorKind::InvalidInput.into())
+        }
+    }
+}
+
+impl fmt::Debug for Atr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Atr")
+            .field("convention", &self.convention)
+            .field("t0", &self.t0)
+            .field("interface", &self.interface_bytes())
+            .field("historical", &self.historical_bytes())
+            .field("tck", &self.tck)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for Atr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ts = match self.convention {
+            Convention::Direct => 0x3B,
+            Convention::Inverse => 0x3F,
+        };
+        writeln!(f, "TS = {:02X} ({:?} convention)", ts, self.convention)?;
+        writeln!(
+            f,
+            "T0 = {:02X} (Y1 = {:X}, K = {})",
+            self.t0,
+            self.t0 >> 4,
+            self.t0 & 0x0F
+        )?;
+        for (i, bytes) in self.interface_bytes().iter().enumerate() {
+            let i = i + 1;
+            for (name, b) in [("TA", bytes.ta), ("TB", bytes.tb), ("TC", bytes.tc)] {
+                if let Some(b) = b {
+                    writeln!(f, "{name}{i} = {b:02X}")?;
+                }
+            }
+            if let Some(td) = bytes.td {
+                writeln!(
+                    f,
+                    "TD{i} = {td:02X} (Y{} = {:X}, T = {})",
+                    i + 1,
+                    td >> 4,
+                    td & 0x0F
+                )?;
+            }
+        }
+        write!(f, "Historical bytes =")?;
+        for x in self.historical_bytes() {
+            write!(f, " {x:02X}")?;
+        }
+        writeln!(f)?;
+        #[cfg(feature = "alloc")]
+        match self.historical() {
+            Ok(HistoricalBytes::StatusIndicatorLast(objects, status)) => {
+                for o in &objects {
+                    fmt_compact(f, o)?;
+                }
+                writeln!(
+                    f,
+                    "    Status indicator = {:02X} {:02X} {:02X}",
+                    status[0], status[1], status[2]
+                )?;
+            }
+            Ok(HistoricalBytes::CompactTlv(objects)) => {
+                for o in &objects {
+                    fmt_compact(f, o)?;
+                }
+            }
+            Ok(HistoricalBytes::DirDataReference(r)) => {
+                writeln!(f, "    DIR data reference = {r:02X}")?;
+            }
+            Ok(HistoricalBytes::Empty | HistoricalBytes::Proprietary(_)) => (),
+            Err(e) => writeln!(f, "    {e}")?,
+        }
+        if let Some(tck) = self.tck {
+            writeln!(f, "TCK = {tck:02X}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+fn fmt_compact(f: &mut fmt::Formatter, tlv: &compact::Tlv) -> fmt::Result {
+    let tag: u8 = tlv.tag().into();
+    write!(f, "    Tag {:X}, len={}, value:", tag, tlv.length())?;
+    for x in tlv.value() {
+        write!(f, "{x:02X}")?;
+    }
+    writeln!(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn parse_t1() -> Result<()> {
+        let raw = hex!("3B F8 13 00 00 81 31 FE 45 4A 43 4F 50 76 32 34 31 B7");
+        let atr = Atr::from_bytes(&raw)?;
+        assert_eq!(Convention::Direct, atr.convention());
+        assert_eq!(0xF8, atr.t0());
+        let interface = atr.interface_bytes();
+        assert_eq!(3, interface.len());
+        assert_eq!(Some(0x13), interface[0].ta());
+        assert_eq!(Some(0x00), interface[0].tb());
+        assert_eq!(Some(0x00), interface[0].tc());
+        assert_eq!(Some(1), interface[0].protocol());
+        assert_eq!(None, interface[1].ta());
+        assert_eq!(Some(0x31), interface[1].td());
+        assert_eq!(Some(0xFE), interface[2].ta());
+        assert_eq!(Some(0x45), interface[2].tb());
+        assert_eq!(None, interface[2].td());
+        assert_eq!(b"JCOPv241", atr.historical_bytes());
+        #[cfg(feature = "alloc")]
+        {
+            assert_eq!(vec![1, 1], atr.protocols());
+            assert_eq!(
+                HistoricalBytes::Proprietary(b"JCOPv241".to_vec()),
+                atr.historical()?
+            );
+        }
+        assert_eq!(Some(0xB7), atr.tck());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_t0() -> Result<()> {
+        let raw = hex!("3F 02 14 50");
+        let atr = Atr::from_bytes(&raw)?;
+        assert_eq!(Convention::Inverse, atr.convention());
+        assert!(atr.interface_bytes().is_empty());
+        #[cfg(feature = "alloc")]
+        assert_eq!(vec![0], atr.protocols());
+        assert_eq!(None, atr.tck());
+        Ok(())
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn historical_bytes() -> Result<()> {
+        let raw = hex!("3B 8A 01 00 31 C0 73 C8 21 10 00 90 00 60");
+        let atr = Atr::from_bytes(&raw)?;
+        let expected = HistoricalBytes::StatusIndicatorLast(
+            vec![
+                compact::Tlv::new(compact::Tag::CARD_SERVICE_DATA, vec![0xC0])?,
+                compact::Tlv::new(compact::Tag::CARD_CAPABILITIES, vec![0xC8, 0x21, 0x10])?,
+            ],
+            [0x00, 0x90, 0x00],
+        );
+        assert_eq!(expected, atr.historical()?);
+
+        assert_eq!(
+            HistoricalBytes::CompactTlv(vec![compact::Tlv::new(
+                compact::Tag::STATUS_INDICATOR,
+                vec![0x00]
+            )?]),
+            HistoricalBytes::parse(&hex!("80 81 00"))?
+        );
+        assert_eq!(
+            HistoricalBytes::DirDataReference(0x42),
+            HistoricalBytes::parse(&hex!("10 42"))?
+        );
+        assert_eq!(HistoricalBytes::Empty, HistoricalBytes::parse(&[])?);
+        assert!(HistoricalBytes::parse(&hex!("00 90 00")).is_err());
+        assert!(HistoricalBytes::parse(&hex!("80 43 00")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_errors() {
+        // wrong TS
+        assert_eq!(
+            Err(ErrorKind::InvalidInput),
+            Atr::parse(&hex!("3C 02 14 50")).0.map_err(|e| e.kind())
+        );
+        // wrong TCK
+        assert_eq!(
+            Err(ErrorKind::InvalidChecksum),
+            Atr::from_bytes(&hex!("3B 8A 01 00 31 C0 73 C8 21 10 00 90 00 61"))
+                .map_err(|e| e.kind())
+        );
+        // missing TCK
+        assert_eq!(
+            Err(ErrorKind::TruncatedInput),
+            Atr::parse(&hex!("3B 8A 01 00 31 C0 73 C8 21 10 00 90 00"))
+                .0
+                .map_err(|e| e.kind())
+        );
+        // trailing bytes
+        assert_eq!(
+            Err(ErrorKind::InvalidInput),
+            Atr::from_bytes(&hex!("3B 02 14 50 00")).map_err(|e| e.kind())
+        );
+        // T0, 16 TDi and 15 historical bytes, then one more TDi
+        let mut raw = hex!("3B 8F").to_vec();
+        raw.extend_from_slice(&[0x80; 15]);
+        raw.push(0x00);
+        raw.extend_from_slice(&[0x42; 15]);
+        assert_eq!(
+            Ok(16),
+            Atr::from_bytes(&raw).map(|atr| atr.interface_bytes().len())
+        );
+        raw.insert(2, 0x80);
+        assert_eq!(
+            Err(ErrorKind::InvalidLength),
+            Atr::from_bytes(&raw).map_err(|e| e.kind())
+        );
+    }
+
+    #[test]
+    fn display() -> Result<()> {
+        let raw = hex!("3B 8A 01 00 31 C0 73 C8 21 10 00 90 00 60");
+        let s = Atr::from_bytes(&raw)?.to_string();
+        assert!(s.contains("TD1 = 01 (Y2 = 0, T = 1)"));
+        assert!(s.contains("Historical bytes = 00 31 C0 73 C8 21 10 00 90 00"));
+        #[cfg(feature = "alloc")]
+        {
+            assert!(s.contains("Tag 7, len=3, value:C82110"));
+            assert!(s.contains("Status indicator = 00 90 00"));
+        }
+        assert!(s.ends_with("TCK = 60\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+//! Fluent construction of nested BER-TLV data
+//!
+
+use alloc::vec::Vec;
+use core::convert::TryInto;
+
+use super::{LengthForm, Tag, Tlv, Value};
+use crate::{ErrorKind, Result, TlvError};
+
+/// Builder for BER-TLV data objects.
+///
+/// Whether the data object is primitive or constructed is given by its tag.
+/// Errors (invalid tag, children given to a primitive data object, ...)
+/// are kept until [`build()`](Self::build()) or [`to_vec()`](Self::to_vec()),
+/// so that a whole tree is built with a single `?`.
+/// See also the [`tlv!`](crate::tlv!) macro.
+///
+/// # Example
+/// ```rust
+/// use iso7816_tlv::ber::TlvBuilder;
+/// # use iso7816_tlv::TlvError;
+///
+/// # fn main() -> Result<(), TlvError> {
+/// // public key template
+/// let encoded = TlvBuilder::new(0x7F49)
+///     .child(TlvBuilder::new(0x81).value([0xC3, 0x5A]))
+///     .child(TlvBuilder::new(0x82).value([0x01, 0x00, 0x01]))
+///     .to_vec()?;
+/// assert_eq!(
+///     vec![0x7F, 0x49, 0x09, 0x81, 0x02, 0xC3, 0x5A, 0x82, 0x03, 0x01, 0x00, 0x01],
+///     encoded
+/// );
+///
+/// assert!(TlvBuilder::new(0x81).child(TlvBuilder::new(0x82)).build().is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+#[must_use]
+pub struct TlvBuilder {
+    tlv: Result<Tlv>,
+}
+
+impl TlvBuilder {
+    /// Starts building an empty data object with given tag.
+    pub fn new<T>(tag: T) -> Self
+    where
+        T: TryInto<Tag>,
+        TlvError: From<T::Error>,
+    {
+        let tlv = tag.try_into().map_err(TlvError::from).and_then(|tag| {
+            let value = if tag.is_constructed() {
+                Value::Constructed(Vec::new())
+            } else {
+                Value::Primitive(Vec::new())
+            };
+            Tlv::new(tag, value)
+        });
+        Self { tlv }
+    }
+
+    /// Sets the value of a primitive data object.
+    pub fn value<V: AsRef<[u8]>>(mut self, value: V) -> Self {
+        self.tlv = self.tlv.and_then(|mut tlv| {
+            *tlv.value_mut().ok_or(ErrorKind::Inconsistant)? = value.as_ref().to_vec();
+            Ok(tlv)
+        });
+        self
+    }
+
+    /// Appends a child to a constructed data object.
+    pub fn child<C: Into<Self>>(mut self, child: C) -> Self {
+        let child = child.into().tlv;
+        self.tlv = self.tlv.and_then(|mut tlv| {
+            tlv.children_mut()
+                .ok_or(ErrorKind::Inconsistant)?
+                .push(child?);
+            Ok(tlv)
+        });
+        self
+    }
+
+    /// Sets the form used to encode the length field,
+    /// see [`Tlv::set_length_form()`].
+    pub fn length_form(mut self, length_form: LengthForm) -> Self {
+        self.tlv = self.tlv.and_then(|mut tlv| {
+            tlv.set_length_form(length_form)?;
+            Ok(tlv)
+        });
+        self
+    }
+
+    /// Builds the data object.
+    /// # Errors
+    /// Fails with the first error encountered while building:
+    /// a tag conversion error, or [`ErrorKind::Inconsistant`] if a value was given
+    /// to a constructed data object or a child to a primitive one.
+    pub fn build(self) -> Result<Tlv> {
+        self.tlv
+    }
+
+    /// Builds the data object and serializes it into a byte vector.
+    /// # Errors
+    /// see [`build()`](Self::build())
+    pub fn to_vec(self) -> Result<Vec<u8>> {
+        self.tlv.map(|tlv| tlv.to_vec())
+    }
+}
+
+impl From<Tlv> for TlvBuilder {
+    fn from(tlv: Tlv) -> Self {
+        Self { tlv: Ok(tlv) }
+    }
+}
+
+/// Builds a [`ber::Tlv`](crate::ber::Tlv) from a declarative description.
+///
+/// Each data object is written `tag => value`, where the value is either
+/// a list of children between braces, separated by commas,
+/// or the content of a primitive data object: a byte array such as `[0x01, 0x02]`,
+/// or any expression between parentheses implementing `AsRef<[u8]>`.
+///
+/// Integer literal tags are checked at compile time, including their consistency with the value.
+/// Other tags (identifiers, expressions between parentheses) are converted when building,
+/// as with [`TlvBuilder::new()`](crate::ber::TlvBuilder::new()).
+///
+/// The macro evaluates to a `Result<Tlv, TlvError>`.
+///
+/// # Example
+/// ```rust
+/// use iso7816_tlv::tlv;
+/// # use iso7816_tlv::TlvError;
+///
+/// # fn main() -> Result<(), TlvError> {
+/// let challenge = vec![0xA5; 8];
+/// // dynamic authentication template
+/// let tlv = tlv! {
+///     0x7C => {
+///         0x81 => (challenge),
+///         0x82 => [],
+///     }
+/// }?;
+/// assert_eq!(14, tlv.to_vec().len());
+///
+/// let tag = "7C";
+/// assert!(tlv!(tag => { 0x82 => [] }).is_ok());
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! tlv {
+    (@builder $tag:tt => { $($ctag:tt => $cvalue:tt),* $(,)? }) => {
+        $crate::ber::TlvBuilder::new($crate::tlv!(@tag $tag, true))
+            $(.child($crate::tlv!(@builder $ctag => $cvalue)))*
+    };
+    (@builder $tag:tt => $value:tt) => {
+        $crate::ber::TlvBuilder::new($crate::tlv!(@tag $tag, false)).value($value)
+    };
+    (@tag $tag:literal, $constructed:expr) => {{
+        const TAG: $crate::ber::Tag = $crate::ber::Tag::__from_literal($tag, $constructed);
+        TAG
+    }};
+    (@tag $tag:tt, $constructed:expr) => {
+        $tag
+    };
+    ($($tlv:tt)+) => {
+        $crate::tlv!(@builder $($tlv)+).build()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::TryFrom;
+
+    #[test]
+    fn builder() -> Result<()> {
+        let tlv = TlvBuilder::new("7F49")
+            .child(TlvBuilder::new(0x81).value([0x01, 0x02]))
+            .child(Tlv::new(Tag::try_from(0x82)?, Value::Primitive(vec![3]))?)
+            .length_form(LengthForm::Indefinite)
+            .build()?;
+        assert_eq!(
+            hex!("7F49 80 81 02 0102 82 01 03 0000").to_vec(),
+            tlv.to_vec()
+        );
+
+        let primitive = TlvBuilder::new(Tag::try_from(0x5A)?).value([0x12, 0x34]);
+        assert_eq!(Ok(hex!("5A 02 1234").to_vec()), primitive.clone().to_vec());
+        assert_eq!(
+            Err(ErrorKind::Inconsistant),
+            primitive
+                .child(TlvBuilder::new(0x81))
+                .build()
+                .map_err(|e| e.kind())
+        );
+        assert_eq!(
+            Err(ErrorKind::Inconsistant),
+            TlvBuilder::new(0x7C)
+                .value([0])
+                .to_vec()
+                .map_err(|e| e.kind())
+        );
+        assert_eq!(
+            Err(ErrorKind::Inconsistant),
+            TlvBuilder::new(0x5A)
+                .length_form(LengthForm::Indefinite)
+                .build()
+                .map_err(|e| e.kind())
+        );
+        // errors of children are kept
+        assert_eq!(
+            Err(ErrorKind::ParseIntError),
+            TlvBuilder::new(0x7C)
+                .child(TlvBuilder::new("XX"))
+                .build()
+                .map_err(|e| e.kind())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tlv_macro() -> Result<()> {
+        let data = [0xAA; 4];
+        let tag = Tag::try_from(0x84)?;
+        let tlv = tlv! {
+            0x6F => {
+                tag => (data),
+                0xA5 => {
+                    0xBF0C => { 0x9F4D => [0x0B, 0x0A] },
+                    0x88 => [0x01]
+                },
+                0x50 => (b"APP"),
+            }
+        }?;
+        let expected = hex!(
+            "6F 18"
+            "84 04 AAAAAAAA"
+            "A5 0B BF0C 05 9F4D 02 0B0A 88 01 01"
+            "50 03 415050"
+        );
+        assert_eq!(expected.to_vec(), tlv.to_vec());
+
+        assert_eq!(
+            Ok(hex!("80 00").to_vec()),
+            tlv!(0x80 => []).map(|t| t.to_vec())
+        );
+        assert_eq!(
+            Err(ErrorKind::Inconsistant),
+            tlv!((0x7C) => [0x01]).map_err(|e| e.kind())
+        );
+        assert_eq!(
+            Err(ErrorKind::InvalidInput),
+            tlv!(("1F") => []).map_err(|e| e.kind())
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,636 @@
+//! serde deserializer mapping BER-TLV data to Rust data structures
+//!
+
+use alloc::vec::Vec;
+use core::convert::TryInto;
+
+use serde::de::{self, Deserialize, DeserializeSeed, IntoDeserializer, Visitor};
+
+use super::ser::name_tag;
+use super::tlv_ref::padding_len;
+use super::{ParseOptions, Tag, TlvRef};
+use crate::{ErrorKind, Result, TlvError};
+
+/// Deserializes BER-TLV data into `T`, following the mapping of [`to_vec()`](super::to_vec()).
+///
+/// Struct fields are looked up by tag, whatever their order in the input.
+/// The content of data objects is only parsed as needed by the deserialized type,
+/// so that constructed tags may hold any value, as found on some cards.
+/// Data objects whose tag does not match a field are ignored, as are '00' and 'FF' padding bytes
+/// around data objects. Missing fields are only accepted for `Option` fields or fields with a default value,
+/// and a data object is only expected once for each field.
+/// Outside of struct fields, an `Option` is `None` when there is no data left.
+/// Integers must be encoded on exactly their size.
+/// The whole input must be used.
+///
+/// # Example
+/// ```rust
+/// use iso7816_tlv::ber;
+/// use serde::Deserialize;
+/// # use iso7816_tlv::TlvError;
+///
+/// #[derive(Deserialize, PartialEq, Debug)]
+/// struct Application<'a> {
+///     #[serde(rename = "0x4F")]
+///     aid: &'a [u8],
+///     #[serde(rename = "0x50")]
+///     label: &'a str,
+///     #[serde(rename = "0x87")]
+///     priority: Option<u8>,
+/// }
+///
+/// # fn main() -> Result<(), TlvError> {
+/// let data = [
+///     0x50, 0x04, 0x56, 0x49, 0x53, 0x41, 0x4F, 0x05, 0xA0, 0x00, 0x00, 0x00, 0x03, 0x87, 0x01, 0x01
+/// ];
+/// let application: Application = ber::from_bytes(&data)?;
+/// assert_eq!("VISA", application.label);
+/// assert_eq!(Some(1), application.priority);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+/// Fails with the parsing error if the input is not valid BER-TLV data,
+/// with [`ErrorKind::MissingField`] if a data object is missing,
+/// with [`ErrorKind::UnexpectedField`] if it is repeated,
+/// with [`ErrorKind::InvalidLength`] if a value is longer than expected,
+/// with [`ErrorKind::TruncatedInput`] if it is shorter,
+/// or with [`ErrorKind::Unsupported`] for data types that cannot be decoded,
+/// including sequence elements not delimited by their encoding (see [`to_vec()`](super::to_vec())).
+pub fn from_bytes<'a, T: Deserialize<'a>>(input: &'a [u8]) -> Result<T> {
+    Deserializer::new(input, 0).deserialize_all(|de| T::deserialize(de))
+}
+
+fn unsupported(name: &'static str) -> TlvError {
+    TlvError::new(ErrorKind::Unsupported).with_detail(name)
+}
+
+struct Deserializer<'de> {
+    input: &'de [u8],
+    // offset of input in the deserialized data, for error reporting
+    offset: usize,
+    // set for the value of a data object matching a struct field,
+    // so that an `Option` field is `Some` even when the value is empty
+    tagged: bool,
+    // set when deserializing a sequence or tuple element,
+    // which must be delimited by its own encoding
+    element: bool,
+}
+
+impl<'de> Deserializer<'de> {
+    fn new(input: &'de [u8], offset: usize) -> Self {
+        Self {
+            input,
+            offset,
+            tagged: false,
+            element: false,
+        }
+    }
+
+    // error located at the current offset
+    fn error(&self, kind: ErrorKind) -> TlvError {
+        TlvError::from(kind).located(self.offset, None, 0)
+    }
+
+    // deserializes a value using the whole input
+    fn deserialize_all<T, F>(mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        let value = f(&mut self)?;
+        if self.input.is_empty() {
+            Ok(value)
+        } else {
+            Err(self.error(ErrorKind::InvalidLength))
+        }
+    }
+
+    // fails for a sequence or tuple element not delimited by its encoding
+    fn delimited(&self, name: &'static str) -> Result<()> {
+        if self.element {
+            Err(unsupported(name))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'de [u8]> {
+        if len > self.input.len() {
+            return Err(self.error(ErrorKind::TruncatedInput));
+        }
+        let (taken, rest) = self.input.split_at(len);
+        self.input = rest;
+        self.offset += len;
+        Ok(taken)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        self.take(N)?
+            .try_into()
+            .map_err(|_| ErrorKind::TruncatedInput.into())
+    }
+
+    fn take_all(&mut self) -> &'de [u8] {
+        self.offset += self.input.len();
+        core::mem::take(&mut self.input)
+    }
+
+    fn skip_padding(&mut self) {
+        let n = padding_len(self.input);
+        self.input = &self.input[n..];
+        self.offset += n;
+    }
+
+    // takes a data object following any padding, along with a deserializer for its value
+    fn take_tlv(&mut self) -> Result<(Tag, Self)> {
+        self.skip_padding();
+        let tlv = TlvRef::read_unchecked(self.input, self.offset, 0, &ParseOptions::new())?;
+        let header_len = tlv.tag().len_as_bytes() + tlv.length_bytes().len();
+        let value = Self::new(tlv.value(), self.offset + header_len);
+        self.take(tlv.as_bytes().len())?;
+        Ok((tlv.tag().clone(), value))
+    }
+
+    fn take_str(&mut self) -> Result<&'de str> {
+        self.delimited("str")?;
+        let e = self.error(ErrorKind::InvalidInput);
+        core::str::from_utf8(self.take_all()).map_err(|_| e)
+    }
+}
+
+macro_rules! deserialize_be_bytes {
+    ($($method:ident: $t:ty => $visit:ident),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                visitor.$visit(<$t>::from_be_bytes(self.take_array()?))
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = TlvError;
+
+    deserialize_be_bytes!(
+        deserialize_i8: i8 => visit_i8,
+        deserialize_i16: i16 => visit_i16,
+        deserialize_i32: i32 => visit_i32,
+        deserialize_i64: i64 => visit_i64,
+        deserialize_u8: u8 => visit_u8,
+        deserialize_u16: u16 => visit_u16,
+        deserialize_u32: u32 => visit_u32,
+        deserialize_u64: u64 => visit_u64
+    );
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value> {
+        // BER-TLV data does not describe the type of values
+        Err(unsupported("any"))
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let [b] = self.take_array()?;
+        visitor.visit_bool(b != 0)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, _: V) -> Result<V::Value> {
+        Err(unsupported("f32"))
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, _: V) -> Result<V::Value> {
+        Err(unsupported("f64"))
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let e = self.error(ErrorKind::InvalidLength);
+        let mut chars = self.take_str()?.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(e),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_borrowed_str(self.take_str()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.delimited("bytes")?;
+        visitor.visit_borrowed_bytes(self.take_all())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    // missing fields are handled by the struct deserializer,
+    // present ones hold a value, even if empty
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.delimited("option")?;
+        if self.tagged || !self.input.is_empty() {
+            visitor.visit_some(self)
+        } else {
+            visitor.visit_none()
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.delimited("unit")?;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.delimited("unit")?;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.delimited("seq")?;
+        visitor.visit_seq(SeqAccess {
+            de: self,
+            len: None,
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(SeqAccess {
+            de: self,
+            len: Some(len),
+        })
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, _: V) -> Result<V::Value> {
+        Err(unsupported("map"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.delimited("struct")?;
+        let mut tlvs = Vec::new();
+        self.skip_padding();
+        while !self.input.is_empty() {
+            let (tag, mut value) = self.take_tlv()?;
+            value.tagged = true;
+            tlvs.push(Some((tag, value)));
+            self.skip_padding();
+        }
+        visitor.visit_map(StructAccess {
+            fields: fields.iter(),
+            tlvs,
+            value: None,
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let (tag, value) = self.take_tlv()?;
+        let mut variant = None;
+        for name in variants {
+            if name_tag(name)? == tag {
+                variant = Some(*name);
+                break;
+            }
+        }
+        let variant =
+            variant.ok_or_else(|| TlvError::new(ErrorKind::InvalidInput).with_detail(name))?;
+        visitor.visit_enum(EnumAccess { variant, value })
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.delimited("ignored")?;
+        self.take_all();
+        visitor.visit_unit()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// Elements of a sequence, up to the end of input, or of a tuple.
+struct SeqAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    len: Option<usize>,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'_, 'de> {
+    type Error = TlvError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        match &mut self.len {
+            Some(0) => return Ok(None),
+            Some(len) => *len -= 1,
+            None if self.de.input.is_empty() => return Ok(None),
+            None => (),
+        }
+        let (tagged, element) = (self.de.tagged, self.de.element);
+        self.de.tagged = false;
+        self.de.element = true;
+        let value = seed.deserialize(&mut *self.de);
+        self.de.tagged = tagged;
+        self.de.element = element;
+        value.map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.len
+    }
+}
+
+/// Fields of a struct, in declaration order, along with the value of the matching data object.
+struct StructAccess<'de> {
+    fields: core::slice::Iter<'static, &'static str>,
+    // parsed data objects, taken when matched with a field
+    tlvs: Vec<Option<(Tag, Deserializer<'de>)>>,
+    value: Option<Deserializer<'de>>,
+}
+
+impl<'de> de::MapAccess<'de> for StructAccess<'de> {
+    type Error = TlvError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        for field in self.fields.by_ref() {
+            let tag = name_tag(field)?;
+            let mut matching = self
+                .tlvs
+                .iter_mut()
+                .filter(|tlv| matches!(tlv, Some((t, _)) if *t == tag));
+            let found = matching.next().and_then(Option::take);
+            if matching.next().is_some() {
+                let e = TlvError::new(ErrorKind::UnexpectedField).with_tag(&tag);
+                return Err(e.with_detail(field));
+            }
+            if let Some((_, value)) = found {
+                self.value = Some(value);
+                return seed.deserialize(field.into_deserializer()).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let value = self
+            .value
+            .take()
+            .unwrap_or_else(|| Deserializer::new(&[], 0));
+        value.deserialize_all(|de| seed.deserialize(de))
+    }
+}
+
+/// Variant selected by the tag of a data object, along with its value.
+struct EnumAccess<'de> {
+    variant: &'static str,
+    value: Deserializer<'de>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess<'de> {
+    type Error = TlvError;
+    type Variant = Deserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Deserializer<'de>)> {
+        let variant = seed.deserialize(IntoDeserializer::<TlvError>::into_deserializer(
+            self.variant,
+        ))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Deserializer<'de> {
+    type Error = TlvError;
+
+    fn unit_variant(self) -> Result<()> {
+        self.deserialize_all(|_| Ok(()))
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        self.deserialize_all(|de| seed.deserialize(de))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_all(|de| de::Deserializer::deserialize_tuple(de, len, visitor))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_all(|de| de::Deserializer::deserialize_struct(de, "", fields, visitor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Key {
+        #[serde(rename = "0x80")]
+        Rsa(u16),
+        #[serde(rename = "0x81")]
+        Ec {
+            #[serde(rename = "0x06")]
+            curve: u8,
+        },
+        #[serde(rename = "0x82")]
+        None,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Chuid {
+        #[serde(rename = "0x30")]
+        fascn: [u8; 25],
+        #[serde(rename = "0x34")]
+        guid: Option<[u8; 16]>,
+        #[serde(rename = "0x35")]
+        expiry: String,
+        #[serde(rename = "0xAC")]
+        key: Key,
+        #[serde(rename = "0x3E")]
+        signature: Vec<u8>,
+        #[serde(rename = "0xFE")]
+        lrc: (),
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let chuid = Chuid {
+            fascn: [0xD4; 25],
+            guid: Some([0x11; 16]),
+            expiry: "20301231".into(),
+            key: Key::Ec { curve: 0x17 },
+            signature: vec![0x5A; 300],
+            lrc: (),
+        };
+        let encoded = super::super::to_vec(&chuid)?;
+        assert_eq!(chuid, from_bytes(&encoded)?);
+        for key in [Key::Rsa(2048), Key::Ec { curve: 1 }, Key::None] {
+            let encoded = super::super::to_vec(&key)?;
+            assert_eq!(key, from_bytes(&encoded)?);
+        }
+        Ok(())
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Record {
+        #[serde(rename = "0x5A")]
+        pan: Option<Vec<u8>>,
+        #[serde(rename = "0x9F4A")]
+        tags: Vec<u16>,
+        #[serde(rename = "0xA5")]
+        keys: Vec<Key>,
+        #[serde(rename = "0x80")]
+        pairs: [(u8, bool); 2],
+    }
+
+    #[test]
+    fn sequences() -> Result<()> {
+        for record in [
+            Record {
+                pan: Some(Vec::new()),
+                tags: vec![0x5F24, 0x9F07],
+                keys: vec![Key::Ec { curve: 1 }, Key::None, Key::Rsa(1024)],
+                pairs: [(1, true), (2, false)],
+            },
+            Record {
+                pan: None,
+                tags: Vec::new(),
+                keys: Vec::new(),
+                pairs: [(0, false); 2],
+            },
+        ] {
+            let encoded = super::super::to_vec(&record)?;
+            assert_eq!(record, from_bytes(&encoded)?);
+        }
+
+        // elements not delimited by their encoding
+        let unsupported = |e: TlvError| e.kind();
+        assert_eq!(
+            Err(ErrorKind::Unsupported),
+            from_bytes::<Vec<String>>(&hex!("3132")).map_err(unsupported)
+        );
+        assert_eq!(
+            Err(ErrorKind::Unsupported),
+            from_bytes::<Vec<Chuid>>(&hex!("FE 00")).map_err(unsupported)
+        );
+        assert_eq!(
+            Err(ErrorKind::Unsupported),
+            from_bytes::<(u8, Option<u8>)>(&hex!("01 02")).map_err(unsupported)
+        );
+
+        // outside of struct fields, no data is `None`
+        assert_eq!(None, from_bytes::<Option<u16>>(&[])?);
+        assert_eq!(Some(0x0102), from_bytes::<Option<u16>>(&hex!("0102"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn custom() {
+        let err = from_bytes::<core::num::NonZeroU8>(&hex!("00")).map(|_| ());
+        let err = err.map_err(|e| (e.kind(), e.to_string()));
+        // long messages are truncated to fit in the error
+        assert_eq!(
+            Err((
+                ErrorKind::Custom,
+                "Serialization error (invalid value: integer `0`, expected a)".into()
+            )),
+            err
+        );
+    }
+
+    #[test]
+    fn decode() -> Result<()> {
+        // any order, unknown data objects are ignored, missing options are None
+        let mut input = hex!("FE 00 99 01 00 AC 04 80 02 0800 35 02 3330 3E 00 30 19").to_vec();
+        input.extend_from_slice(&[0xD4; 25]);
+        let chuid: Chuid = from_bytes(&input)?;
+        assert_eq!(None, chuid.guid);
+        assert_eq!(Key::Rsa(2048), chuid.key);
+        assert_eq!("30", chuid.expiry);
+
+        // padding around data objects is skipped
+        let mut padded =
+            hex!("00 FE 00 FF 99 01 00 AC 05 FF 80 02 0800 35 02 3330 3E 00 0000 30 19").to_vec();
+        padded.extend_from_slice(&[0xD4; 25]);
+        padded.push(0xFF);
+        assert_eq!(chuid, from_bytes(&padded)?);
+        // repeated data objects are rejected for fields only
+        let mut repeated = hex!("35 02 3330 99 00").to_vec();
+        repeated.extend_from_slice(&input);
+        let e = from_bytes::<Chuid>(&repeated).err();
+        assert_eq!(
+            Some((ErrorKind::UnexpectedField, Some(&crate::ber_tag!(0x35)))),
+            e.as_ref().map(|e| (e.kind(), e.tag()))
+        );
+        assert_eq!(chuid, from_bytes(&repeated[4..])?);
+
+        let err = |input: &[u8]| from_bytes::<Chuid>(input).map_err(|e| e.kind());
+        assert_eq!(
+            Err(ErrorKind::MissingField),
+            err(&input[..input.len() - 27])
+        );
+        // truncated and overlong values
+        let mut fascn = hex!("FE 00 AC 02 8200 35 00 3E 00 30 18").to_vec();
+        fascn.extend_from_slice(&[0xD4; 24]);
+        assert_eq!(Err(ErrorKind::TruncatedInput), err(&fascn));
+        let located = from_bytes::<Chuid>(&fascn).err().and_then(|e| e.offset());
+        assert_eq!(Some(fascn.len()), located);
+        fascn[10] = 0x1A;
+        fascn.extend_from_slice(&[0xD4; 2]);
+        assert_eq!(Err(ErrorKind::InvalidLength), err(&fascn));
+        // unknown variant
+        assert_eq!(
+            Err(ErrorKind::InvalidInput),
+            from_bytes::<Key>(&hex!("83 00")).map_err(|e| e.kind())
+        );
+        // invalid BER-TLV data
+        assert_eq!(Err(ErrorKind::TruncatedInput), err(&hex!("FE 01")));
+        assert_eq!(
+            Err(ErrorKind::InvalidLength),
+            from_bytes::<u16>(&hex!("000102")).map_err(|e| e.kind())
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,443 @@
+//! Incremental decoding of BER-TLV data received in chunks
+//!
+
+use alloc::vec::Vec;
+
+use untrusted::{Input, Reader};
+
+use super::length::read_len;
+use super::{Padding, ParseOptions, Tag, Tlv, TlvRef};
+use crate::{ErrorKind, Result, TlvError};
+
+/// Outcome of [`Decoder::decode()`].
+#[derive(PartialEq, Debug, Clone)]
+pub enum Decoded {
+    /// A top-level BER-TLV data object has been fully received
+    Tlv(Tlv),
+    /// At least this number of bytes is needed to go on decoding
+    NeedMore(usize),
+}
+
+enum Header {
+    Incomplete(usize),
+    Complete { len: Option<usize>, size: usize },
+}
+
+/// Push-style BER-TLV decoder, for data received in fragments
+/// (T=1 chaining, extended length chains, ...).
+///
+/// Chunks are appended with [`push()`](Self::push()), then
+/// [`decode()`](Self::decode()) is called until it reports that more data is needed.
+/// Decoding goes on from where it stopped, already received bytes are not parsed again
+/// until a top-level data object is complete.
+///
+/// Limits of the [`ParseOptions`] (see [`with_options()`](Self::with_options())) are checked
+/// as data is received: a top-level data object longer than allowed is rejected as soon as
+/// a length field claims too much data, and data objects in the indefinite length form
+/// nested too deeply are rejected before their content is buffered.
+/// Values in the definite length form are only parsed once fully received,
+/// so that the maximum length also bounds the buffered data: decoders created with
+/// [`new()`](Self::new()) accept up to [`DEFAULT_MAX_LEN`](Self::DEFAULT_MAX_LEN) bytes.
+///
+/// With [`Padding::Preserve`], padding received before a top-level data object
+/// is kept in the returned [`Tlv`], as it cannot be attached to the preceding one
+/// once returned.
+///
+/// # Example
+/// ```rust
+/// use iso7816_tlv::ber::{Decoded, Decoder, Tlv};
+/// # use iso7816_tlv::TlvError;
+///
+/// # fn main() -> Result<(), TlvError> {
+/// let mut decoder = Decoder::new();
+/// decoder.push(&[0x6F, 0x07, 0x84]);
+/// assert_eq!(Decoded::NeedMore(6), decoder.decode()?);
+///
+/// decoder.push(&[0x05, 0xA0, 0x00, 0x00, 0x00, 0x03, 0x80]);
+/// if let Decoded::Tlv(tlv) = decoder.decode()? {
+///     assert_eq!(9, tlv.to_vec().len());
+/// }
+/// // start of next data object
+/// assert_eq!(Decoded::NeedMore(1), decoder.decode()?);
+/// assert_eq!(1, decoder.pending());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    buf: Vec<u8>,
+    // position of the next header to scan in buf
+    pos: usize,
+    // number of indefinite length data objects not yet ended
+    depth: usize,
+    // padding received before the current top-level data object, if preserved
+    padding: Vec<u8>,
+    options: ParseOptions,
+}
+
+impl Decoder {
+    /// Maximum length of a top-level data object for decoders created with [`new()`](Self::new()),
+    /// far above the size of any APDU.
+    pub const DEFAULT_MAX_LEN: usize = 0x10_0000;
+
+    /// Creates a new decoder, using default parsing options,
+    /// and accepting top-level data objects of up to [`DEFAULT_MAX_LEN`](Self::DEFAULT_MAX_LEN) bytes
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_options(ParseOptions::new().max_len(Self::DEFAULT_MAX_LEN))
+    }
+
+    /// Creates a new decoder, using given parsing options.
+    ///
+    /// Received data is buffered up to the maximum length set in `options`,
+    /// which is not limited by default.
+    #[must_use]
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self {
+            buf: Vec::new(),
+            pos: 0,
+            depth: 0,
+            padding: Vec::new(),
+            options,
+        }
+    }
+
+    /// Appends a chunk of data
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Number of bytes received but not yet returned as part of a data object
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.padding.len() + self.buf.len()
+    }
+
+    /// Drops any pending data and starts over
+    pub fn reset(&mut self) {
+        self.buf.clear();
+        self.pos = 0;
+        self.depth = 0;
+        self.padding.clear();
+    }
+
+    fn scan_header(data: &[u8]) -> Result<Header> {
+        let mut r = Reader::new(Input::from(data));
+        let tag = match Tag::read(&mut r) {
+            Ok(t) => t,
+            Err(e) if e.kind() == ErrorKind::TruncatedInput => return Ok(Header::Incomplete(1)),
+            Err(e) => return Err(e),
+        };
+        let tag_len = tag.len_as_bytes();
+        let Some(&first) = data.get(tag_len) else {
+            return Ok(Header::Incomplete(1));
+        };
+        let len_bytes = if first & 0x80 == 0 {
+            0
+        } else {
+            usize::from(first & 0x7F)
+        };
+        let size = tag_len + 1 + len_bytes;
+        // too long length fields are rejected by read_len
+        if data.len() < size && len_bytes <= 4 {
+            return Ok(Header::Incomplete(size - data.len()));
+        }
+        let len = read_len(&mut r, false)?;
+        if len.is_none() && !tag.is_constructed() {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+        Ok(Header::Complete { len, size })
+    }
+
+    // scans headers until the end of the current top-level data object
+    fn scan(&mut self) -> Result<Decoded> {
+        loop {
+            if self.pos > self.buf.len() {
+                return Ok(Decoded::NeedMore(self.pos - self.buf.len()));
+            }
+            if self.depth == 0 && self.pos > 0 {
+                return self.complete();
+            }
+            if self.options.padding != Padding::Reject {
+                self.skip_padding()?;
+            }
+            let data = &self.buf[self.pos..];
+            if self.depth > 0 && data.first() == Some(&0) {
+                // end-of-contents
+                match data.get(1) {
+                    None => return Ok(Decoded::NeedMore(1)),
+                    Some(0) => {
+                        self.advance(2)?;
+                        self.depth -= 1;
+                        continue;
+                    }
+                    Some(_) => {
+                        let e = TlvError::from(ErrorKind::InvalidInput);
+                        return Err(e.located(self.pos, None, self.depth));
+                    }
+                }
+            }
+            if self.depth > self.options.max_depth {
+                let e = TlvError::from(ErrorKind::DepthLimitExceeded);
+                return Err(e.located(self.pos, None, self.depth));
+            }
+            let header =
+                Self::scan_header(data).map_err(|e| e.located(self.pos, None, self.depth))?;
+            match header {
+                Header::Incomplete(n) => return Ok(Decoded::NeedMore(n)),
+                Header::Complete {
+                    len: Some(len),
+                    size,
+                } => self.advance(size.saturating_add(len))?,
+                Header::Complete { len: None, size } => {
+                    self.advance(size)?;
+                    self.depth += 1;
+                }
+            }
+        }
+    }
+
+    // moves past `n` bytes of the current top-level data object, checking its length
+    fn advance(&mut self, n: usize) -> Result<()> {
+        let pos = self.pos.saturating_add(n);
+        if pos > self.options.max_len {
+            let e = TlvError::from(ErrorKind::LimitExceeded);
+            return Err(e.located(self.pos, None, self.depth));
+        }
+        self.pos = pos;
+        Ok(())
+    }
+
+    // padding before top-level data objects is dropped, unless preserved,
+    // inside values in the indefinite length form, only 'FF' bytes are padding
+    fn skip_padding(&mut self) -> Result<()> {
+        let data = &self.buf[self.pos..];
+        if self.depth == 0 {
+            let n = data.iter().take_while(|&&b| b == 0 || b == 0xFF).count();
+            if self.options.padding == Padding::Preserve {
+                if self.padding.len().saturating_add(n) > self.options.max_len {
+                    return Err(TlvError::from(ErrorKind::LimitExceeded).located(0, None, 0));
+                }
+                self.padding.extend_from_slice(&data[..n]);
+            }
+            self.buf.drain(..n);
+        } else {
+            self.pos += data.iter().take_while(|&&b| b == 0xFF).count();
+        }
+        Ok(())
+    }
+
+    fn complete(&mut self) -> Result<Decoded> {
+        let mut tlv =
+            TlvRef::read(&self.buf[..self.pos], 0, 0, &self.options).map(|t| Tlv::from(&t))?;
+        tlv.set_outer_padding(0, &self.padding);
+        self.buf.drain(..self.pos);
+        self.pos = 0;
+        self.padding.clear();
+        Ok(Decoded::Tlv(tlv))
+    }
+
+    /// Decodes the next top-level BER-TLV data object.
+    /// Returns [`Decoded::NeedMore`] if it has not been fully received yet.
+    ///
+    /// # Errors
+    /// Fails if received data is not valid BER-TLV.
+    /// Error offsets are relative to the beginning of the top-level data object being decoded.
+    /// The decoder is then reset.
+    pub fn decode(&mut self) -> Result<Decoded> {
+        let ret = self.scan();
+        if ret.is_err() {
+            self.reset();
+        }
+        ret
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ber::LengthForm;
+
+    #[test]
+    fn byte_by_byte() -> Result<()> {
+        let input = hex!(
+            "6F 1A 84 0E 315041592E5359532E4444463031 A5 08 88 01 02 5F2D 02 656E"
+            "7F22 80 010100 A1 80 0101AA 0000 0000"
+            "9F7F 81 02 0102"
+        );
+        let expected = Tlv::parse_all(&input);
+        assert_eq!(3, expected.len());
+
+        let mut decoder = Decoder::new();
+        let mut received = Vec::new();
+        for b in &input {
+            decoder.push(&[*b]);
+            while let Decoded::Tlv(tlv) = decoder.decode()? {
+                received.push(tlv);
+            }
+        }
+        assert_eq!(expected, received);
+        assert_eq!(LengthForm::Indefinite, received[1].length_form());
+        assert_eq!(0, decoder.pending());
+        Ok(())
+    }
+
+    #[test]
+    fn need_more() -> Result<()> {
+        let mut decoder = Decoder::new();
+        assert_eq!(Decoded::NeedMore(1), decoder.decode()?);
+        // two bytes tag
+        decoder.push(&hex!("5F"));
+        assert_eq!(Decoded::NeedMore(1), decoder.decode()?);
+        decoder.push(&hex!("2D"));
+        assert_eq!(Decoded::NeedMore(1), decoder.decode()?);
+        // two bytes length field
+        decoder.push(&hex!("82"));
+        assert_eq!(Decoded::NeedMore(2), decoder.decode()?);
+        decoder.push(&hex!("01"));
+        assert_eq!(Decoded::NeedMore(1), decoder.decode()?);
+        decoder.push(&hex!("00"));
+        assert_eq!(Decoded::NeedMore(256), decoder.decode()?);
+        decoder.push(&[0_u8; 200]);
+        assert_eq!(Decoded::NeedMore(56), decoder.decode()?);
+        decoder.push(&[0_u8; 56]);
+        assert!(matches!(decoder.decode()?, Decoded::Tlv(_)));
+
+        // end-of-contents
+        decoder.push(&hex!("A1 80 00"));
+        assert_eq!(Decoded::NeedMore(1), decoder.decode()?);
+        decoder.push(&hex!("00"));
+        assert!(matches!(decoder.decode()?, Decoded::Tlv(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let mut decoder = Decoder::with_options(ParseOptions::new().padding(Padding::Reject));
+        decoder.push(&hex!("01 80"));
+        assert_eq!(
+            Err(ErrorKind::InvalidLength),
+            decoder.decode().map_err(|e| e.kind())
+        );
+        assert_eq!(0, decoder.pending());
+
+        decoder.push(&hex!("00"));
+        assert_eq!(
+            Err(ErrorKind::InvalidInput),
+            decoder.decode().map_err(|e| e.kind())
+        );
+
+        // child exceeds its parent
+        decoder.push(&hex!("A1 02 01 02 00 00"));
+        assert_eq!(
+            Err(ErrorKind::Inconsistant),
+            decoder.decode().map_err(|e| e.kind())
+        );
+
+        decoder.push(&hex!("A1 80 01 00 00 01"));
+        assert_eq!(
+            Err((ErrorKind::InvalidInput, Some(4), 1)),
+            decoder
+                .decode()
+                .map_err(|e| (e.kind(), e.offset(), e.depth()))
+        );
+    }
+
+    #[test]
+    fn limits() {
+        // nesting rejected as soon as the limit is reached, whatever follows
+        let mut decoder = Decoder::new();
+        decoder.push(&[0x20, 0x80].repeat(ParseOptions::DEFAULT_MAX_DEPTH));
+        assert_eq!(Ok(Decoded::NeedMore(1)), decoder.decode());
+        decoder.push(&hex!("20 80"));
+        assert_eq!(
+            Err((ErrorKind::DepthLimitExceeded, 33)),
+            decoder.decode().map_err(|e| (e.kind(), e.depth()))
+        );
+
+        let mut decoder = Decoder::with_options(ParseOptions::new().max_depth(1));
+        decoder.push(&hex!("20 80 20 80 20 80"));
+        assert_eq!(
+            Err((ErrorKind::DepthLimitExceeded, Some(4))),
+            decoder.decode().map_err(|e| (e.kind(), e.offset()))
+        );
+
+        // claimed lengths are checked before the value is received
+        let mut decoder = Decoder::with_options(ParseOptions::new().max_len(16));
+        decoder.push(&hex!("04 84 FFFFFFFF"));
+        assert_eq!(
+            Err(ErrorKind::LimitExceeded),
+            decoder.decode().map_err(|e| e.kind())
+        );
+        decoder.push(&hex!("20 80 04 08"));
+        assert_eq!(Ok(Decoded::NeedMore(8)), decoder.decode());
+        decoder.push(&[0_u8; 8]);
+        decoder.push(&hex!("04 03"));
+        assert_eq!(
+            Err((ErrorKind::LimitExceeded, Some(12))),
+            decoder.decode().map_err(|e| (e.kind(), e.offset()))
+        );
+        decoder.push(&hex!("04 0E"));
+        assert_eq!(Ok(Decoded::NeedMore(14)), decoder.decode());
+
+        // buffered data is limited by default
+        let mut decoder = Decoder::new();
+        decoder.push(&hex!("04 83 0FFFFB"));
+        assert_eq!(Ok(Decoded::NeedMore(0x0F_FFFB)), decoder.decode());
+        decoder.reset();
+        decoder.push(&hex!("24 83 0FFFFC"));
+        assert_eq!(
+            Err(ErrorKind::LimitExceeded),
+            decoder.decode().map_err(|e| e.kind())
+        );
+    }
+
+    #[test]
+    fn padding() -> Result<()> {
+        let mut decoder = Decoder::new();
+        decoder.push(&hex!("00 FF 01 01 AA FF 00 21 80 FF 01 00 FF"));
+        assert_eq!(
+            Decoded::Tlv(Tlv::from_bytes(&hex!("01 01 AA"))?),
+            decoder.decode()?
+        );
+        assert_eq!(Decoded::NeedMore(1), decoder.decode()?);
+        decoder.push(&hex!("00 00 FF"));
+        let expected = Tlv::from_bytes(&hex!("21 80 01 00 00 00"))?;
+        assert_eq!(Decoded::Tlv(expected), decoder.decode()?);
+        assert_eq!(Decoded::NeedMore(1), decoder.decode()?);
+        assert_eq!(0, decoder.pending());
+
+        // preserved padding is attached to the following data object
+        let mut decoder = Decoder::with_options(ParseOptions::new().padding(Padding::Preserve));
+        let encoded = |decoded: Decoded| match decoded {
+            Decoded::Tlv(tlv) => tlv.to_vec(),
+            Decoded::NeedMore(_) => Vec::new(),
+        };
+        decoder.push(&hex!("00 FF 01 01 AA FF"));
+        assert_eq!(hex!("00 FF 01 01 AA").to_vec(), encoded(decoder.decode()?));
+        decoder.push(&hex!("00 21 80 FF 01 00 FF 00 00"));
+        assert_eq!(
+            hex!("FF 00 21 80 FF 01 00 FF 00 00").to_vec(),
+            encoded(decoder.decode()?)
+        );
+        decoder.push(&[0xFF; 4]);
+        assert_eq!(Decoded::NeedMore(1), decoder.decode()?);
+        assert_eq!(4, decoder.pending());
+
+        // preserved padding is bounded as well
+        let options = ParseOptions::new().padding(Padding::Preserve).max_len(3);
+        let mut decoder = Decoder::with_options(options);
+        decoder.push(&[0xFF; 4]);
+        assert_eq!(
+            Err(ErrorKind::LimitExceeded),
+            decoder.decode().map_err(|e| e.kind())
+        );
+        Ok(())
+    }
+}
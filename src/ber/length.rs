@@ -0,0 +1,103 @@
+//! Length field definition and utilities for BER-TLV data as defined in [ISO7816-4]
+//!
+
+use untrusted::Reader;
+
+use crate::{ErrorKind, Result};
+
+/// Encoding form of the length field of a BER-TLV data object.
+/// > In the definite form, the length field encodes the number of bytes of the value field.
+/// > In the indefinite form (constructed encoding only), the length field is set to '80'
+/// > and the value field is ended by two end-of-contents bytes '00 00'.
+///
+/// The indefinite form is not part of ISO/IEC 7816, but is emitted
+/// by some legacy cards and CER encoders.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum LengthForm {
+    /// Length field encodes the value length
+    #[default]
+    Definite,
+    /// Length field is '80', value field is ended by '00 00'
+    Indefinite,
+}
+
+/// Reads a length field.
+/// Returns `None` for the indefinite form.
+/// If `minimal` is set, definite form length fields must be encoded on the minimal number of bytes.
+pub(super) fn read_len(r: &mut Reader, minimal: bool) -> Result<Option<usize>> {
+    let mut ret: usize = 0;
+    let x = r.read_byte()?;
+    if x & 0x80 == 0 {
+        ret = x as usize;
+    } else {
+        let n_bytes = x as usize & 0x7f;
+        if n_bytes == 0 {
+            return Ok(None);
+        }
+        if n_bytes > 4 {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+        for i in 0..n_bytes {
+            let x = r.read_byte()?;
+            if minimal && i == 0 && x == 0 {
+                return Err(ErrorKind::InvalidLength.into());
+            }
+            ret = ret << 8 | x as usize;
+        }
+        if minimal && ret < 0x80 {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+    }
+    Ok(Some(ret))
+}
+
+/// Decodes a whole definite form length field.
+#[cfg(feature = "alloc")]
+pub(super) fn decode_len(field: &[u8]) -> Option<usize> {
+    untrusted::Input::from(field)
+        .read_all(ErrorKind::InvalidLength.into(), |r| read_len(r, false))
+        .ok()
+        .flatten()
+}
+
+/// Number of bytes of the definite form length field encoding `len`.
+pub(super) fn len_length(len: usize) -> usize {
+    if len < 0x80 {
+        1
+    } else {
+        1 + len.to_be_bytes().iter().skip_while(|&x| *x == 0).count()
+    }
+}
+
+/// Encodes `len` as a definite form length field, using the minimal number of bytes.
+#[allow(clippy::cast_possible_truncation)]
+pub(super) fn encode_len(len: usize, buf: &mut [u8; 9]) -> &[u8] {
+    let n = len_length(len);
+    if n == 1 {
+        buf[0] = len as u8;
+    } else {
+        let bytes = len.to_be_bytes();
+        buf[0] = 0x80 | (n - 1) as u8;
+        buf[1..n].copy_from_slice(&bytes[bytes.len() - (n - 1)..]);
+    }
+    &buf[..n]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode() {
+        let mut buf = [0; 9];
+        assert_eq!(&[0x00], encode_len(0, &mut buf));
+        assert_eq!(&[0x7F], encode_len(127, &mut buf));
+        assert_eq!(&[0x81, 0x80], encode_len(128, &mut buf));
+        assert_eq!(&[0x81, 0xFF], encode_len(255, &mut buf));
+        assert_eq!(&[0x82, 0x01, 0x00], encode_len(256, &mut buf));
+        assert_eq!(&[0x83, 0x01, 0x00, 0x00], encode_len(65_536, &mut buf));
+        for l in [0, 127, 128, 255, 256, 65_535, 65_536, 16_777_216] {
+            assert_eq!(len_length(l), encode_len(l, &mut buf).len());
+        }
+    }
+}
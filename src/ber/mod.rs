@@ -7,11 +7,80 @@
 //! [iso7816-4]: https://www.iso.org/standard/54550.html
 
 // internal organization
+#[cfg(feature = "alloc")]
+mod builder;
+#[cfg(all(feature = "serde", feature = "alloc"))]
+mod de;
+#[cfg(feature = "alloc")]
+mod decoder;
+mod length;
+mod options;
+#[cfg(feature = "alloc")]
+mod path;
+#[cfg(all(feature = "serde", feature = "alloc"))]
+mod ser;
 mod tag;
+#[cfg(feature = "alloc")]
+mod template;
+#[cfg(feature = "alloc")]
 mod tlv;
+mod tlv_ref;
+#[cfg(feature = "alloc")]
 mod value;
 
 // custom reexport (structs at same level for users)
-pub use tag::{Class, Tag};
+#[cfg(feature = "alloc")]
+pub use builder::TlvBuilder;
+#[cfg(all(feature = "serde", feature = "alloc"))]
+pub use de::from_bytes;
+#[cfg(feature = "alloc")]
+pub use decoder::{Decoded, Decoder};
+/// # Example
+/// ```rust
+/// use core::convert::TryFrom;
+/// use iso7816_tlv::ber::{BerTlv, Tlv};
+///
+/// #[derive(BerTlv)]
+/// struct Proprietary {
+///     #[tlv(tag = 0x88)]
+///     sfi: [u8; 1],
+///     #[tlv(tag = 0x5F2D, optional)]
+///     language: Option<Vec<u8>>,
+/// }
+///
+/// #[derive(BerTlv)]
+/// #[tlv(tag = 0x6F)]
+/// struct Fci {
+///     #[tlv(tag = 0x84)]
+///     name: Vec<u8>,
+///     #[tlv(constructed = 0xA5)]
+///     proprietary: Proprietary,
+/// }
+///
+/// let tlv = Tlv::from_bytes(&[0x6F, 0x09, 0x84, 0x02, 0xA0, 0x00, 0xA5, 0x03, 0x88, 0x01, 0x01])?;
+/// let fci = Fci::try_from(&tlv)?;
+/// assert_eq!(vec![0xA0, 0x00], fci.name);
+/// assert_eq!([0x01], fci.proprietary.sfi);
+/// assert_eq!(None, fci.proprietary.language);
+/// assert_eq!(tlv, Tlv::from(fci));
+/// # Ok::<(), iso7816_tlv::TlvError>(())
+/// ```
+#[cfg(feature = "derive")]
+pub use iso7816_tlv_derive::BerTlv;
+pub use length::LengthForm;
+pub use options::{Padding, ParseOptions};
+#[cfg(feature = "alloc")]
+pub use path::{Match, Path};
+#[cfg(all(feature = "serde", feature = "alloc"))]
+pub use ser::to_vec;
+pub use tag::{Class, Form, Tag};
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub use template::__private;
+#[cfg(feature = "alloc")]
+pub use template::Template;
+#[cfg(feature = "alloc")]
 pub use tlv::Tlv;
+pub use tlv_ref::{Children, TlvIter, TlvRef};
+#[cfg(feature = "alloc")]
 pub use value::Value;
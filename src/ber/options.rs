@@ -0,0 +1,188 @@
+//! Parsing policy for BER-TLV data
+//!
+
+/// Handling of padding bytes around BER-TLV data objects.
+/// > Before, between or after BER-TLV data objects,
+/// > '00' or 'FF' bytes without any meaning may occur
+/// > (for example, due to erased or modified TLV-coded data objects).
+///
+/// Padding is recognized at top level and inside constructed values.
+/// Inside values in the indefinite length form, only 'FF' bytes are padding,
+/// as '00' starts the end-of-contents bytes.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Padding {
+    /// Padding bytes are parsed as a tag, and fail for '00'
+    Reject,
+    /// Padding bytes are skipped
+    #[default]
+    Skip,
+    /// Padding bytes are skipped, and kept in the parsed [`Tlv`](super::Tlv),
+    /// so that serializing it gives back the parsed data.
+    /// Padding between top-level data objects is attached to the preceding one,
+    /// and only serialized along with it when it is not the child of another data object.
+    Preserve,
+}
+
+/// Options controlling how BER-TLV data is parsed,
+/// see [`TlvRef::parse_with()`](super::TlvRef::parse_with()) and [`TlvIter::with_options()`](super::TlvIter::with_options()),
+/// or `Tlv::parse_with()` and `Decoder::with_options()` for owned data objects.
+///
+/// Default options are lenient and match [`TlvRef::parse()`](super::TlvRef::parse()):
+/// any length encoding and any tag are accepted, padding bytes are skipped,
+/// and only the nesting depth is limited, to [`DEFAULT_MAX_DEPTH`](Self::DEFAULT_MAX_DEPTH),
+/// so that hostile input cannot exhaust the stack.
+/// [`strict()`](Self::strict()) options only accept minimal length encodings
+/// and [ISO7816-4] compliant tags.
+///
+/// Limits are checked while parsing, and fail with [`ErrorKind::LimitExceeded`](crate::ErrorKind::LimitExceeded),
+/// or [`ErrorKind::DepthLimitExceeded`](crate::ErrorKind::DepthLimitExceeded) for the nesting depth.
+///
+/// # Example
+/// ```rust
+/// use iso7816_tlv::ber::{ParseOptions, TlvRef};
+/// use iso7816_tlv::ErrorKind;
+///
+/// // non-minimal length field
+/// let data = [0x5A, 0x81, 0x02, 0x12, 0x34];
+/// assert!(TlvRef::parse_with(&data, &ParseOptions::new()).0.is_ok());
+///
+/// let (tlv, _) = TlvRef::parse_with(&data, &ParseOptions::strict());
+/// assert_eq!(Err(ErrorKind::InvalidLength), tlv.map_err(|e| e.kind()));
+///
+/// let (tlv, _) = TlvRef::parse_with(&data, &ParseOptions::new().max_len(4));
+/// assert_eq!(Err(ErrorKind::LimitExceeded), tlv.map_err(|e| e.kind()));
+/// ```
+///
+/// [ISO7816-4]: https://www.iso.org/standard/54550.html
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[must_use]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ParseOptions {
+    pub(super) max_depth: usize,
+    pub(super) max_len: usize,
+    pub(super) max_children: usize,
+    pub(super) minimal_lengths: bool,
+    pub(super) definite_lengths: bool,
+    pub(super) minimal_tags: bool,
+    pub(super) iso7816_tags: bool,
+    pub(super) padding: Padding,
+    pub(super) original_lengths: bool,
+}
+
+impl ParseOptions {
+    /// Default maximum nesting depth.
+    /// Data objects found on smart cards are seldom nested more than a few levels deep.
+    pub const DEFAULT_MAX_DEPTH: usize = 32;
+
+    // walks already parsed data
+    pub(super) const UNLIMITED: Self = Self::new().max_depth(usize::MAX);
+
+    /// Lenient options, accepting any BER-TLV data
+    /// not nested deeper than [`DEFAULT_MAX_DEPTH`](Self::DEFAULT_MAX_DEPTH).
+    pub const fn new() -> Self {
+        Self {
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            max_len: usize::MAX,
+            max_children: usize::MAX,
+            minimal_lengths: false,
+            definite_lengths: false,
+            minimal_tags: false,
+            iso7816_tags: false,
+            padding: Padding::Skip,
+            original_lengths: false,
+        }
+    }
+
+    /// Strict options, only accepting minimal length encodings and [ISO7816-4] compliant tags.
+    ///
+    /// [ISO7816-4]: https://www.iso.org/standard/54550.html
+    pub const fn strict() -> Self {
+        Self::new().minimal_lengths(true).iso7816_tags(true)
+    }
+
+    /// Options only accepting the Distinguished Encoding Rules (DER):
+    /// minimal length encodings in the definite form, and minimal tag encodings.
+    /// Padding is rejected.
+    pub const fn der() -> Self {
+        Self::new()
+            .minimal_lengths(true)
+            .definite_lengths(true)
+            .minimal_tags(true)
+            .padding(Padding::Reject)
+    }
+
+    /// Sets the maximum nesting depth of data objects.
+    /// Top-level data objects have a depth of 0, their children a depth of 1, and so on.
+    ///
+    /// Parsing recurses into constructed data objects,
+    /// so this bounds the stack used when parsing.
+    pub const fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the maximum length of a top-level data object, header included.
+    pub const fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Sets the maximum number of children of a constructed data object.
+    pub const fn max_children(mut self, max_children: usize) -> Self {
+        self.max_children = max_children;
+        self
+    }
+
+    /// Whether to reject length fields that are not encoded on the minimal number of bytes,
+    /// such as `81 05`, with [`ErrorKind::InvalidLength`](crate::ErrorKind::InvalidLength).
+    pub const fn minimal_lengths(mut self, minimal_lengths: bool) -> Self {
+        self.minimal_lengths = minimal_lengths;
+        self
+    }
+
+    /// Whether to reject length fields in the indefinite form,
+    /// with [`ErrorKind::InvalidLength`](crate::ErrorKind::InvalidLength).
+    pub const fn definite_lengths(mut self, definite_lengths: bool) -> Self {
+        self.definite_lengths = definite_lengths;
+        self
+    }
+
+    /// Whether to reject tags that are not encoded on the minimal number of bytes
+    /// (see [`Tag::is_minimal()`](super::Tag::is_minimal())),
+    /// with [`ErrorKind::InvalidInput`](crate::ErrorKind::InvalidInput).
+    pub const fn minimal_tags(mut self, minimal_tags: bool) -> Self {
+        self.minimal_tags = minimal_tags;
+        self
+    }
+
+    /// Whether to reject tags that are not [ISO7816-4] compliant
+    /// (see [`Tag::iso7816_compliant()`](super::Tag::iso7816_compliant())),
+    /// with [`ErrorKind::InvalidInput`](crate::ErrorKind::InvalidInput).
+    ///
+    /// [ISO7816-4]: https://www.iso.org/standard/54550.html
+    pub const fn iso7816_tags(mut self, iso7816_tags: bool) -> Self {
+        self.iso7816_tags = iso7816_tags;
+        self
+    }
+
+    /// Sets how `00` and `FF` padding bytes around data objects are handled.
+    pub const fn padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Whether parsed owned data objects keep the length fields
+    /// that are not encoded in the minimal definite form,
+    /// so that `Tlv::to_vec_original()` gives them back.
+    /// By default, length fields are re-encoded when serializing.
+    pub const fn original_lengths(mut self, original_lengths: bool) -> Self {
+        self.original_lengths = original_lengths;
+        self
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,309 @@
+//! Path queries over nested BER-TLV data
+//!
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::ControlFlow;
+use core::str::FromStr;
+
+use super::{Tag, Tlv, Value};
+use crate::{ErrorKind, Result, TlvError};
+
+#[derive(PartialEq, Debug, Clone)]
+struct Step {
+    // `None` matches any tag
+    tag: Option<Tag>,
+    // index among the siblings matching the tag
+    index: Option<usize>,
+}
+
+impl FromStr for Step {
+    type Err = TlvError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (tag, index) = match s.strip_suffix(']') {
+            Some(s) => {
+                let (tag, index) = s.split_once('[').ok_or(ErrorKind::InvalidInput)?;
+                (tag, Some(index.parse::<usize>()?))
+            }
+            None => (s, None),
+        };
+        let tag = match tag {
+            "*" => None,
+            "" => return Err(ErrorKind::InvalidInput.into()),
+            _ => Some(Tag::try_from(tag)?),
+        };
+        Ok(Self { tag, index })
+    }
+}
+
+/// Sequence of tags locating data objects in a BER-TLV tree.
+///
+/// The first step of a path matches the data object a query starts from
+/// (or the data objects of a slice, see [`select()`](Self::select())),
+/// each following step matches children of the data objects matched by the previous one.
+///
+/// A path is built from a slice of [`Tag`]s, or parsed from a string
+/// of hex tags separated by `/`, where each step can be:
+/// - a tag, such as `6F`, matching all children with this tag,
+/// - the wildcard `*`, matching all children,
+/// - a tag or wildcard followed by an index, such as `61[2]`, only matching
+///   the child of given index among those matched (indices start at 0).
+///
+/// # Example
+/// ```rust
+/// use iso7816_tlv::ber::{Path, Tlv};
+/// # use iso7816_tlv::{ErrorKind, TlvError};
+///
+/// # fn main() -> Result<(), TlvError> {
+/// // two application templates, each holding an AID
+/// let data = [
+///     0x70, 0x0C, 0x61, 0x04, 0x4F, 0x02, 0xA0, 0x01, 0x61, 0x04, 0x4F, 0x02, 0xA0, 0x02,
+/// ];
+/// let record = Tlv::from_bytes(&data)?;
+///
+/// let path: Path = "70/61[1]/4F".parse()?;
+/// let aid = record.select(&path).ok_or(ErrorKind::InvalidInput)?;
+/// assert_eq!(&[0x4F, 0x02, 0xA0, 0x02], &aid.to_vec()[..]);
+///
+/// assert_eq!(2, record.select_all(&"70/*/4F".parse()?).len());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct Path {
+    steps: Vec<Step>,
+}
+
+impl Path {
+    /// Get the number of steps of the path
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns `true` if the path has no step, in which case it matches nothing
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Finds the first data object of `tlvs` or of their descendants matching the path.
+    #[must_use]
+    pub fn select<'a>(&self, tlvs: &'a [Tlv]) -> Option<&'a Tlv> {
+        let mut ret = None;
+        let _ = Self::walk(tlvs, &self.steps, &mut |tlv| {
+            ret = Some(tlv);
+            ControlFlow::Break(())
+        });
+        ret
+    }
+
+    /// Finds all data objects of `tlvs` or of their descendants matching the path,
+    /// in depth-first order.
+    #[must_use]
+    pub fn select_all<'a>(&self, tlvs: &'a [Tlv]) -> Vec<&'a Tlv> {
+        let mut ret = Vec::new();
+        let _ = Self::walk(tlvs, &self.steps, &mut |tlv| {
+            ret.push(tlv);
+            ControlFlow::Continue(())
+        });
+        ret
+    }
+
+    fn walk<'a, F>(tlvs: &'a [Tlv], steps: &[Step], f: &mut F) -> ControlFlow<()>
+    where
+        F: FnMut(&'a Tlv) -> ControlFlow<()>,
+    {
+        let Some((step, next)) = steps.split_first() else {
+            return ControlFlow::Continue(());
+        };
+        let matching = tlvs
+            .iter()
+            .filter(|tlv| step.tag.iter().all(|t| t == tlv.tag()))
+            .enumerate()
+            .filter(|(i, _)| step.index.iter().all(|index| index == i));
+        for (_, tlv) in matching {
+            if next.is_empty() {
+                f(tlv)?;
+            } else if let Value::Constructed(children) = tlv.value() {
+                Self::walk(children, next, f)?;
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            if i > 0 {
+                write!(f, "/")?;
+            }
+            match &step.tag {
+                Some(tag) => {
+                    for b in tag.to_bytes() {
+                        write!(f, "{b:02X}")?;
+                    }
+                }
+                None => write!(f, "*")?,
+            }
+            if let Some(index) = step.index {
+                write!(f, "[{index}]")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<&[Tag]> for Path {
+    fn from(tags: &[Tag]) -> Self {
+        let steps = tags
+            .iter()
+            .map(|t| Step {
+                tag: Some(t.clone()),
+                index: None,
+            })
+            .collect();
+        Self { steps }
+    }
+}
+
+impl FromStr for Path {
+    type Err = TlvError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let steps = s.split('/').map(Step::from_str).collect::<Result<_>>()?;
+        Ok(Self { steps })
+    }
+}
+
+impl TryFrom<&str> for Path {
+    type Error = TlvError;
+
+    fn try_from(s: &str) -> Result<Self> {
+        s.parse()
+    }
+}
+
+/// Data object found by a search, along with its parents.
+///
+/// See [`Tlv::search_by()`].
+#[derive(PartialEq, Debug, Clone)]
+pub struct Match<'a> {
+    tlv: &'a Tlv,
+    parents: Vec<&'a Tlv>,
+}
+
+impl<'a> Match<'a> {
+    pub(crate) fn new(tlv: &'a Tlv, parents: Vec<&'a Tlv>) -> Self {
+        Self { tlv, parents }
+    }
+
+    /// Get the data object found
+    #[must_use]
+    pub fn tlv(&self) -> &'a Tlv {
+        self.tlv
+    }
+
+    /// Get the parents of the data object found, from the searched data object
+    /// down to its direct parent. This is empty if the searched data object matched.
+    #[must_use]
+    pub fn parents(&self) -> &[&'a Tlv] {
+        &self.parents
+    }
+
+    /// Get the path locating the data object found from the searched data object.
+    /// Every step is indexed, so that the path only matches this data object.
+    #[must_use]
+    pub fn path(&self) -> Path {
+        let root = self.parents.first().copied().unwrap_or(self.tlv);
+        let mut siblings = core::slice::from_ref(root);
+        let mut steps = Vec::with_capacity(self.parents.len() + 1);
+        for &tlv in self.parents.iter().chain(core::iter::once(&self.tlv)) {
+            let index = siblings
+                .iter()
+                .filter(|x| x.tag() == tlv.tag())
+                .position(|x| core::ptr::eq(x, tlv));
+            steps.push(Step {
+                tag: Some(tlv.tag().clone()),
+                index,
+            });
+            if let Value::Constructed(children) = tlv.value() {
+                siblings = children;
+            }
+        }
+        Path { steps }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn fci() -> Result<Tlv> {
+        // FCI template holding two FCI proprietary templates
+        Tlv::from_bytes(&hex!(
+            "6F 18"
+            "84 02 A000"
+            "A5 0A 88 01 01 BF0C 04 9F4D 01 0B"
+            "A5 06 88 01 02 87 01 01"
+        ))
+    }
+
+    #[test]
+    fn parse() -> Result<()> {
+        let path = Path::try_from("6F/*/bf0c[1]")?;
+        assert_eq!(3, path.len());
+        assert_eq!(
+            Step {
+                tag: Some(Tag::try_from(0xBF0C)?),
+                index: Some(1)
+            },
+            path.steps[2]
+        );
+        assert_eq!(None, path.steps[1].tag);
+        assert_eq!("6F/*/BF0C[1]", path.to_string());
+        let tags = [Tag::try_from(0x6F)?, Tag::try_from(0xA5)?];
+        assert_eq!(Path::try_from("6F/A5")?, Path::from(&tags[..]));
+
+        for bad in [
+            "", "6F/", "/6F", "6F//A5", "6F[", "6F[x]", "[1]", "GG", "6F]",
+        ] {
+            assert!(Path::try_from(bad).is_err(), "{}", bad);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn select() -> Result<()> {
+        let fci = fci()?;
+        let found = fci
+            .select(&"6F/A5/BF0C/9F4D".parse()?)
+            .ok_or(ErrorKind::InvalidInput)?;
+        assert_eq!(&Value::Primitive(vec![0x0B]), found.value());
+        assert_eq!(2, fci.select_all(&"6F/A5/88".parse()?).len());
+
+        // 88 appears in both A5 templates
+        let found = fci
+            .select(&"6F/A5[1]/88".parse()?)
+            .ok_or(ErrorKind::InvalidInput)?;
+        assert_eq!(&Value::Primitive(vec![0x02]), found.value());
+        assert_eq!(1, fci.select_all(&"6F/*[1]".parse()?).len());
+        assert_eq!(4, fci.select_all(&"6F/*/*".parse()?).len());
+
+        // first step matches the data object itself
+        assert_eq!(Some(&fci), fci.select(&"*".parse()?));
+        assert!(fci.select(&"A5/88".parse()?).is_none());
+        assert!(fci.select(&"6F/A5[2]".parse()?).is_none());
+        // primitive data objects have no children
+        assert!(fci.select(&"6F/84/*".parse()?).is_none());
+
+        let all = [fci.clone(), fci];
+        assert_eq!(4, Path::try_from("6F/A5/88")?.select_all(&all).len());
+        assert_eq!(2, Path::try_from("6F[1]/A5/88")?.select_all(&all).len());
+        Ok(())
+    }
+}
@@ -0,0 +1,499 @@
+//! serde serializer mapping Rust data structures to BER-TLV data
+//!
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use serde::ser::{self, Impossible, Serialize};
+
+use super::length::encode_len;
+use super::Tag;
+use crate::{ErrorKind, Result, TlvError};
+
+/// Serializes `value` into BER-TLV data.
+///
+/// Each field of a struct is encoded as a data object, whose tag is given by the field name
+/// in hex, with an optional `0x` prefix, usually set with `#[serde(rename = "0x5F24")]`.
+/// Fields are encoded in declaration order, and `None` fields are omitted.
+/// The value of a data object is:
+/// - the concatenated encoding of its fields for a struct, whatever the tag form,
+/// - big-endian bytes for integers, `00` or `FF` for booleans,
+/// - UTF-8 bytes for strings, the bytes themselves for byte arrays and `Vec<u8>`,
+/// - the concatenated encoding of its elements for sequences and tuples,
+/// - a single data object tagged with the variant name for enums (as an ASN.1 CHOICE),
+/// - empty for unit types.
+///
+/// As sequence and tuple elements are not framed, they must be delimited by their own encoding:
+/// integers, booleans, enums, and tuples or arrays of them.
+/// Floating point numbers and maps are not supported.
+///
+/// # Example
+/// ```rust
+/// use iso7816_tlv::ber;
+/// use serde::Serialize;
+/// # use iso7816_tlv::TlvError;
+///
+/// #[derive(Serialize)]
+/// struct Application {
+///     #[serde(rename = "0x4F")]
+///     aid: [u8; 5],
+///     #[serde(rename = "0x50")]
+///     label: &'static str,
+///     #[serde(rename = "0x87")]
+///     priority: Option<u8>,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Template {
+///     #[serde(rename = "0x61")]
+///     application: Application,
+/// }
+///
+/// # fn main() -> Result<(), TlvError> {
+/// let template = Template {
+///     application: Application {
+///         aid: [0xA0, 0x00, 0x00, 0x00, 0x03],
+///         label: "VISA",
+///         priority: None,
+///     },
+/// };
+/// assert_eq!(
+///     vec![
+///         0x61, 0x0D, 0x4F, 0x05, 0xA0, 0x00, 0x00, 0x00, 0x03,
+///         0x50, 0x04, 0x56, 0x49, 0x53, 0x41
+///     ],
+///     ber::to_vec(&template)?
+/// );
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+/// Fails with [`ErrorKind::InvalidInput`] if a field or variant name is not a valid tag,
+/// with [`ErrorKind::Unsupported`] for data types that cannot be encoded,
+/// including sequence elements such as strings or structs,
+/// or with the error raised by the `Serialize` implementation of `value`.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let mut serializer = Serializer::default();
+    value.serialize(&mut serializer)?;
+    Ok(serializer.output)
+}
+
+// tag given by a field or variant name
+pub(super) fn name_tag(name: &'static str) -> Result<Tag> {
+    let hex = name
+        .strip_prefix("0x")
+        .or_else(|| name.strip_prefix("0X"))
+        .unwrap_or(name);
+    Tag::try_from(hex).map_err(|e| e.with_kind(ErrorKind::InvalidInput).with_detail(name))
+}
+
+fn unsupported(name: &'static str) -> TlvError {
+    TlvError::new(ErrorKind::Unsupported).with_detail(name)
+}
+
+#[derive(Default)]
+struct Serializer {
+    output: Vec<u8>,
+    // set when serializing `None`, so that the field is omitted
+    none: bool,
+    // set when serializing a sequence or tuple element,
+    // which must be delimited by its own encoding
+    element: bool,
+}
+
+impl Serializer {
+    fn write_tlv(&mut self, tag: &Tag, value: &[u8]) {
+        let mut buf = [0; 9];
+        self.output.extend_from_slice(tag.to_bytes());
+        self.output
+            .extend_from_slice(encode_len(value.len(), &mut buf));
+        self.output.extend_from_slice(value);
+    }
+
+    // fails for the value of a sequence or tuple element not delimited by its encoding
+    fn delimited(&self, name: &'static str) -> Result<()> {
+        if self.element {
+            Err(unsupported(name))
+        } else {
+            Ok(())
+        }
+    }
+
+    // encoding of a nested value, `None` if omitted
+    fn nested<T: Serialize + ?Sized>(value: &T) -> Result<Option<Vec<u8>>> {
+        let mut serializer = Self::default();
+        value.serialize(&mut serializer)?;
+        Ok(if serializer.none {
+            None
+        } else {
+            Some(serializer.output)
+        })
+    }
+}
+
+macro_rules! serialize_be_bytes {
+    ($($method:ident: $t:ty),*) => {
+        $(
+            fn $method(self, v: $t) -> Result<()> {
+                self.output.extend_from_slice(&v.to_be_bytes());
+                Ok(())
+            }
+        )*
+    };
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Ok = ();
+    type Error = TlvError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = VariantSerializer<'a>;
+    type SerializeMap = Impossible<(), TlvError>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = VariantSerializer<'a>;
+
+    serialize_be_bytes!(
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64
+    );
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.output.push(if v { 0xFF } else { 0x00 });
+        Ok(())
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<()> {
+        Err(unsupported("f32"))
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<()> {
+        Err(unsupported("f64"))
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.delimited("str")?;
+        self.serialize_bytes(v.as_bytes())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.delimited("bytes")?;
+        self.output.extend_from_slice(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        self.delimited("option")?;
+        self.none = true;
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        self.delimited("option")?;
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        self.delimited("unit")
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<()> {
+        self.delimited("unit")
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, variant: &'static str) -> Result<()> {
+        self.write_tlv(&name_tag(variant)?, &[]);
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        let tag = name_tag(variant)?;
+        let value = Serializer::nested(value)?.unwrap_or_default();
+        self.write_tlv(&tag, &value);
+        Ok(())
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self> {
+        self.delimited("seq")?;
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<VariantSerializer<'a>> {
+        VariantSerializer::new(self, variant)
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(unsupported("map"))
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self> {
+        self.delimited("struct")?;
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<VariantSerializer<'a>> {
+        VariantSerializer::new(self, variant)
+    }
+}
+
+impl ser::SerializeSeq for &mut Serializer {
+    type Ok = ();
+    type Error = TlvError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let mut element = Serializer {
+            element: true,
+            ..Serializer::default()
+        };
+        value.serialize(&mut element)?;
+        self.output.extend_from_slice(&element.output);
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for &mut Serializer {
+    type Ok = ();
+    type Error = TlvError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for &mut Serializer {
+    type Ok = ();
+    type Error = TlvError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for &mut Serializer {
+    type Ok = ();
+    type Error = TlvError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        let tag = name_tag(key)?;
+        if let Some(v) = Serializer::nested(value)? {
+            self.write_tlv(&tag, &v);
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Serializes the content of a tuple or struct variant,
+/// written as a data object tagged with the variant name on `end()`.
+struct VariantSerializer<'a> {
+    parent: &'a mut Serializer,
+    tag: Tag,
+    value: Serializer,
+}
+
+impl<'a> VariantSerializer<'a> {
+    fn new(parent: &'a mut Serializer, variant: &'static str) -> Result<Self> {
+        Ok(Self {
+            parent,
+            tag: name_tag(variant)?,
+            value: Serializer::default(),
+        })
+    }
+
+    fn end(self) {
+        self.parent.write_tlv(&self.tag, &self.value.output);
+    }
+}
+
+impl ser::SerializeTupleVariant for VariantSerializer<'_> {
+    type Ok = ();
+    type Error = TlvError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(&mut &mut self.value, value)
+    }
+
+    fn end(self) -> Result<()> {
+        VariantSerializer::end(self);
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<'_> {
+    type Ok = ();
+    type Error = TlvError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeStruct::serialize_field(&mut &mut self.value, key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        VariantSerializer::end(self);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    enum Key {
+        #[serde(rename = "0x80")]
+        Rsa(u16),
+        #[serde(rename = "0x81")]
+        Ec {
+            #[serde(rename = "0x06")]
+            curve: u8,
+        },
+        #[serde(rename = "0x82")]
+        None,
+    }
+
+    #[derive(Serialize)]
+    struct Chuid {
+        #[serde(rename = "0x30")]
+        fascn: [u8; 25],
+        #[serde(rename = "0x34")]
+        guid: Option<[u8; 16]>,
+        #[serde(rename = "0x35")]
+        expiry: &'static str,
+        #[serde(rename = "0xFE")]
+        lrc: (),
+    }
+
+    #[derive(Serialize)]
+    struct Untagged {
+        field: u8,
+    }
+
+    #[derive(Serialize)]
+    struct Object {
+        // primitive tag, but PIV data objects hold BER-TLV data
+        #[serde(rename = "0x53")]
+        chuid: Chuid,
+    }
+
+    #[test]
+    fn encode() -> Result<()> {
+        let object = Object {
+            chuid: Chuid {
+                fascn: [0xD4; 25],
+                guid: None,
+                expiry: "20301231",
+                lrc: (),
+            },
+        };
+        let mut expected = hex!("53 27 30 19").to_vec();
+        expected.extend_from_slice(&[0xD4; 25]);
+        expected.extend_from_slice(&hex!("35 08 3230333031323331 FE 00"));
+        assert_eq!(expected, to_vec(&object)?);
+
+        assert_eq!(hex!("1234").to_vec(), to_vec(&0x1234_u16)?);
+        assert_eq!(hex!("FF 00").to_vec(), to_vec(&(true, false))?);
+        assert_eq!(hex!("80 02 0800").to_vec(), to_vec(&Key::Rsa(2048))?);
+        assert_eq!(
+            hex!("81 03 06 01 17").to_vec(),
+            to_vec(&Key::Ec { curve: 0x17 })?
+        );
+        assert_eq!(hex!("82 00").to_vec(), to_vec(&Key::None)?);
+
+        assert_eq!(
+            Err(ErrorKind::Unsupported),
+            to_vec(&1.0_f32).map_err(|e| e.kind())
+        );
+        let err = to_vec(&Untagged { field: 0 }).map_err(|e| e.kind());
+        assert_eq!(Err(ErrorKind::InvalidInput), err);
+
+        // sequence elements must be delimited
+        assert_eq!(hex!("0001 0002").to_vec(), to_vec(&[1_u16, 2])?);
+        assert_eq!(
+            hex!("82 00 82 00").to_vec(),
+            to_vec(&[Key::None, Key::None])?
+        );
+        let unsupported = |e: TlvError| (e.kind(), e.to_string());
+        assert_eq!(
+            Err((ErrorKind::Unsupported, "Unsupported data type (str)".into())),
+            to_vec(&["ab", "c"]).map_err(unsupported)
+        );
+        assert_eq!(
+            Err(ErrorKind::Unsupported),
+            to_vec(&vec![Untagged { field: 0 }]).map_err(|e| e.kind())
+        );
+        assert_eq!(
+            Err(ErrorKind::Unsupported),
+            to_vec(&(1_u8, vec![2_u8])).map_err(|e| e.kind())
+        );
+        Ok(())
+    }
+}
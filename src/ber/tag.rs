@@ -4,7 +4,7 @@
 use core::convert::TryFrom;
 use core::fmt;
 
-use crate::{Result, TlvError};
+use crate::{ErrorKind, Result, TlvError};
 use untrusted::Reader;
 
 /// Class of a BER-TLV Tag field.
@@ -13,7 +13,8 @@ use untrusted::Reader;
 /// > - The value 01 indicates a data object of the application class.
 /// > - The value 10 indicates a data object of the context-specific class.
 /// > - The value 11 indicates a data object of the private class.
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Class {
     /// Universal class, not defined in ISO/IEC 7816
     Universal,
@@ -25,8 +26,8 @@ pub enum Class {
     Private,
 }
 
-impl From<u8> for Class {
-    fn from(val: u8) -> Self {
+impl Class {
+    const fn from_byte(val: u8) -> Self {
         match val & Tag::CLASS_MASK {
             0b0000_0000 => Self::Universal,
             0b0100_0000 => Self::Application,
@@ -36,11 +37,90 @@ impl From<u8> for Class {
     }
 }
 
+impl From<u8> for Class {
+    fn from(val: u8) -> Self {
+        Self::from_byte(val)
+    }
+}
+
+impl From<Class> for u8 {
+    /// Class bits of the first byte of the tag field
+    fn from(class: Class) -> Self {
+        class.to_bits()
+    }
+}
+
+impl Class {
+    const fn to_bits(self) -> u8 {
+        match self {
+            Self::Universal => 0b0000_0000,
+            Self::Application => 0b0100_0000,
+            Self::ContextSpecific => 0b1000_0000,
+            Self::Private => 0b1100_0000,
+        }
+    }
+}
+
+/// Encoding of a BER-TLV data object, given by its Tag field.
+/// > Bit 6 of the first byte of the tag field indicates an encoding.
+/// > - The value 0 indicates a primitive encoding of the data object, i.e., the value field is not encoded in BER - TLV .
+/// > - The value 1 indicates a constructed encoding of the data object, i.e., the value field is encoded in BER - TLV
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Form {
+    /// The value field is not encoded in BER-TLV
+    Primitive,
+    /// The value field is encoded in BER-TLV
+    Constructed,
+}
+
+impl Form {
+    const fn from_byte(val: u8) -> Self {
+        if val & Tag::CONSTRUCTED_MASK == 0 {
+            Self::Primitive
+        } else {
+            Self::Constructed
+        }
+    }
+
+    const fn to_bits(self) -> u8 {
+        match self {
+            Self::Primitive => 0,
+            Self::Constructed => Tag::CONSTRUCTED_MASK,
+        }
+    }
+}
+
+impl From<u8> for Form {
+    fn from(val: u8) -> Self {
+        Self::from_byte(val)
+    }
+}
+
+impl From<Form> for u8 {
+    /// Encoding bit of the first byte of the tag field
+    fn from(form: Form) -> Self {
+        form.to_bits()
+    }
+}
+
+impl From<bool> for Form {
+    /// `true` for constructed, `false` for primitive
+    fn from(constructed: bool) -> Self {
+        if constructed {
+            Self::Constructed
+        } else {
+            Self::Primitive
+        }
+    }
+}
+
 /// Tag for BER-TLV data as defined in [ISO7816-4].
 ///
 /// Tags can be generated using the [`TryFrom`][TryFrom] trait
 /// from integer types (see [Trait Implementations](struct.Tag.html#implementations) for valid input types)
 /// or hex [str][str].
+/// Constant tags are created with [`Self::try_from_u32`] or the [`ber_tag!`](crate::ber_tag!) macro.
 ///
 ///
 /// [TryFrom]: https://doc.rust-lang.org/std/convert/trait.TryFrom.html
@@ -64,7 +144,8 @@ impl From<u8> for Class {
 /// assert!(Tag::try_from("7f22").is_ok());
 ///
 /// assert!(Tag::try_from("error ").is_err());
-/// assert!(Tag::try_from("7fffff01").is_err());
+/// // tags longer than 3 bytes require the `long-tags` feature
+/// assert_eq!(cfg!(feature = "long-tags"), Tag::try_from("7fffff01").is_ok());
 /// assert!(Tag::try_from("7f80ff").is_err());
 /// // now more permissive interface :
 /// assert!(Tag::try_from("7f00").is_ok());
@@ -74,8 +155,9 @@ impl From<u8> for Class {
 /// ```
 #[derive(PartialEq, Clone)]
 pub struct Tag {
-    raw: [u8; 3],
-    len: usize,
+    // right aligned
+    raw: [u8; 8],
+    len: u8,
 }
 
 impl Tag {
@@ -83,11 +165,16 @@ impl Tag {
     const CONSTRUCTED_MASK: u8 = 0b0010_0000;
     const VALUE_MASK: u8 = 0b0001_1111;
     const MORE_BYTES_MASK: u8 = 0b1000_0000;
+    #[cfg(not(feature = "long-tags"))]
+    const MAX_LEN: usize = 3;
+    // up to 5 subsequent bytes for 32 bits tag numbers
+    #[cfg(feature = "long-tags")]
+    const MAX_LEN: usize = 6;
 
     /// serializes the tag as byte array
     #[must_use]
-    pub fn to_bytes(&self) -> &[u8] {
-        &self.raw[self.raw.len() - self.len..]
+    pub const fn to_bytes(&self) -> &[u8] {
+        self.raw.split_at(self.raw.len() - self.len_as_bytes()).1
     }
 
     /// length of the tag as byte array
@@ -117,8 +204,9 @@ impl Tag {
     /// #
     /// ```
     #[must_use]
-    pub fn len_as_bytes(&self) -> usize {
-        self.len
+    #[allow(clippy::cast_lossless)]
+    pub const fn len_as_bytes(&self) -> usize {
+        self.len as usize
     }
 
     /// Wether the tag is constructed or not
@@ -143,8 +231,8 @@ impl Tag {
     /// #
     /// ```
     #[must_use]
-    pub fn is_constructed(&self) -> bool {
-        !matches!(self.raw[3 - self.len] & Self::CONSTRUCTED_MASK, 0)
+    pub const fn is_constructed(&self) -> bool {
+        !matches!(self.first_byte() & Self::CONSTRUCTED_MASK, 0)
     }
 
     /// Valid tags are defined as follow:
@@ -175,11 +263,11 @@ impl Tag {
     /// # }
     /// #
     #[must_use]
-    pub fn iso7816_compliant(&self) -> bool {
+    pub const fn iso7816_compliant(&self) -> bool {
         let first_byte_ok = if self.len == 1 {
-            (self.raw[2] & Self::VALUE_MASK) != Self::VALUE_MASK
+            (self.raw[7] & Self::VALUE_MASK) != Self::VALUE_MASK
         } else {
-            (self.raw[2] & Self::VALUE_MASK) == Self::VALUE_MASK
+            (self.first_byte() & Self::VALUE_MASK) == Self::VALUE_MASK
         };
 
         let other_bytes_ok = match self.len {
@@ -187,15 +275,15 @@ impl Tag {
             2 => {
                 // In two-byte tag fields, the second byte consists of bit 8 set to 0 and bits 7 to 1 encoding a number greater
                 // than thirty. The second byte is valued from '1F' to '7F; the tag number is from 31 to 127
-                !(self.raw[2] < 0x1F_u8 || self.raw[2] > 0x7F_u8)
+                !(self.raw[7] < 0x1F_u8 || self.raw[7] > 0x7F_u8)
             }
             3 => {
                 // The second byte is valued from '81' to 'FF'
-                if self.raw[2] < 0x81_u8 {
+                if self.raw[6] < 0x81_u8 {
                     false
                 } else {
                     //and the third byte from '00' to '7F';
-                    self.raw[3] & Self::MORE_BYTES_MASK == 0
+                    self.raw[7] & Self::MORE_BYTES_MASK == 0
                 }
             }
             _ => false, //rfu
@@ -221,40 +309,274 @@ impl Tag {
     /// #
     /// ```
     #[must_use]
-    pub fn class(&self) -> Class {
-        self.raw[3 - self.len].into()
+    pub const fn class(&self) -> Class {
+        Class::from_byte(self.first_byte())
+    }
+
+    /// Get the tag encoding.
+    #[must_use]
+    pub const fn form(&self) -> Form {
+        Form::from_byte(self.first_byte())
+    }
+
+    /// Get the tag number, i.e., the tag without its class and encoding.
+    /// # Example
+    /// ```rust
+    /// use std::convert::TryFrom;
+    /// use iso7816_tlv::ber::{Class, Form, Tag};
+    /// # use iso7816_tlv::TlvError;
+    ///
+    /// # fn main() -> Result<(), TlvError> {
+    /// let tag = Tag::try_from(0x5F2D)?;
+    /// assert_eq!(45, tag.number());
+    /// assert_eq!(Class::Application, tag.class());
+    /// assert_eq!(Form::Primitive, tag.form());
+    ///
+    /// assert_eq!(12, Tag::try_from(0xAC)?.number());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    #[allow(clippy::cast_lossless)]
+    pub const fn number(&self) -> u32 {
+        if self.len == 1 {
+            return (self.raw[7] & Self::VALUE_MASK) as u32;
+        }
+        let mut number = 0;
+        let mut i = self.raw.len() + 1 - self.len_as_bytes();
+        while i < self.raw.len() {
+            number = number << 7 | (self.raw[i] & !Self::MORE_BYTES_MASK) as u32;
+            i += 1;
+        }
+        number
+    }
+
+    /// Creates a tag from its class, encoding and number, using the shortest encoding.
+    ///
+    /// ```rust
+    /// use std::convert::TryFrom;
+    /// use iso7816_tlv::ber::{Class, Form, Tag};
+    /// # use iso7816_tlv::TlvError;
+    ///
+    /// # fn main() -> Result<(), TlvError> {
+    /// // [APPLICATION 45] (primitive)
+    /// let tag = Tag::from_parts(Class::Application, Form::Primitive, 45)?;
+    /// assert_eq!(Tag::try_from(0x5F2D)?, tag);
+    ///
+    /// let tag = Tag::from_parts(Class::ContextSpecific, Form::Constructed, 2)?;
+    /// assert_eq!(&[0xA2], tag.to_bytes());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// This method returns `Err(ErrorKind::TagIsRFU)` if the tag number does not fit
+    /// in 3 bytes (i.e., is above 16383) without the `long-tags` feature.
+    #[allow(clippy::cast_lossless)]
+    pub const fn from_parts(class: Class, form: Form, number: u32) -> Result<Self> {
+        let first = (class.to_bits() | form.to_bits()) as u64;
+        if number < Self::VALUE_MASK as u32 {
+            return Self::from_u64(first | number as u64);
+        }
+        let mut value = first | Self::VALUE_MASK as u64;
+        let mut groups = 1;
+        while groups < 5 && (number >> (7 * groups)) != 0 {
+            groups += 1;
+        }
+        while groups > 0 {
+            groups -= 1;
+            let mut byte = ((number >> (7 * groups)) & 0x7F) as u64;
+            if groups > 0 {
+                byte |= Self::MORE_BYTES_MASK as u64;
+            }
+            value = value << 8 | byte;
+        }
+        Self::from_u64(value)
+    }
+
+    /// Whether the tag is encoded on the minimal number of bytes,
+    /// as required by the Distinguished Encoding Rules (DER).
+    /// # Example
+    /// ```rust
+    /// use std::convert::TryFrom;
+    /// use iso7816_tlv::ber::Tag;
+    /// # use iso7816_tlv::TlvError;
+    ///
+    /// # fn main() -> Result<(), TlvError> {
+    /// assert!(Tag::try_from(0x5F2D)?.is_minimal());
+    /// // tag number 30 fits in a single byte
+    /// assert!(!Tag::try_from(0x9F1E)?.is_minimal());
+    /// // leading zero bits
+    /// assert!(!Tag::try_from(0x5F_80_2D)?.is_minimal());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub const fn is_minimal(&self) -> bool {
+        // encodings of the same tag number only differ by their length
+        match Self::from_parts(self.class(), self.form(), self.number()) {
+            Ok(tag) => tag.len == self.len,
+            Err(_) => false,
+        }
+    }
+
+    const fn first_byte(&self) -> u8 {
+        self.raw[self.raw.len() - self.len_as_bytes()]
+    }
+
+    /// Creates a tag from an integer, in a `const` context.
+    ///
+    /// This performs the same checks as the [`TryFrom`] implementations,
+    /// and can be used for defining constants (see also the [`ber_tag!`](crate::ber_tag!) macro):
+    ///
+    /// ```
+    /// use iso7816_tlv::ber::Tag;
+    ///
+    /// const CARDHOLDER_NAME: Tag = match Tag::try_from_u32(0x5F20) {
+    ///     Ok(tag) => tag,
+    ///     Err(e) => panic!(),
+    /// };
+    /// assert_eq!(&[0x5F, 0x20], CARDHOLDER_NAME.to_bytes());
+    /// ```
+    ///
+    /// # Errors
+    /// This method returns `Err(ErrorKind::InvalidInput)` if `v` is not a valid tag encoding,
+    /// and `Err(ErrorKind::TagIsRFU)` if it is more than 3 bytes long
+    /// without the `long-tags` feature.
+    #[allow(clippy::cast_lossless)]
+    pub const fn try_from_u32(v: u32) -> Result<Self> {
+        Self::from_u64(v as u64)
+    }
+
+    const fn from_u64(v: u64) -> Result<Self> {
+        let raw = v.to_be_bytes();
+        let mut len = raw.len();
+        while len > 0 && raw[raw.len() - len] == 0 {
+            len -= 1;
+        }
+
+        let invalid = if len == 0 {
+            Some(ErrorKind::InvalidInput)
+        } else if len > Self::MAX_LEN {
+            Some(ErrorKind::TagIsRFU)
+        } else if len == 1 {
+            if (raw[7] & Self::VALUE_MASK) == Self::VALUE_MASK {
+                Some(ErrorKind::InvalidInput)
+            } else {
+                None
+            }
+        } else {
+            // subsequent bytes have bit 8 set, except the last one
+            let mut i = raw.len() + 1 - len;
+            let mut more = true;
+            while i < raw.len() - 1 {
+                more &= (raw[i] & Self::MORE_BYTES_MASK) == Self::MORE_BYTES_MASK;
+                i += 1;
+            }
+            if !more || (raw[7] & Self::MORE_BYTES_MASK) == Self::MORE_BYTES_MASK {
+                Some(ErrorKind::InvalidInput)
+            } else if len == 6 && (raw[3] & !Self::MORE_BYTES_MASK) > 0x0F {
+                // tag number above 32 bits
+                Some(ErrorKind::TagIsRFU)
+            } else {
+                None
+            }
+        };
+        match invalid {
+            Some(kind) => Err(TlvError::new(kind)),
+            // at most `MAX_LEN` bytes
+            #[allow(clippy::cast_possible_truncation)]
+            None => Ok(Self {
+                raw,
+                len: len as u8,
+            }),
+        }
+    }
+
+    // used by the `tlv!` macro to check literal tags at compile time
+    #[doc(hidden)]
+    #[must_use]
+    pub const fn __from_literal(v: u64, constructed: bool) -> Self {
+        match Self::from_u64(v) {
+            Ok(tag) => {
+                assert!(
+                    tag.is_constructed() == constructed,
+                    "BER-TLV tag does not match the value (primitive or constructed)"
+                );
+                tag
+            }
+            Err(_) => panic!("invalid BER-TLV tag"),
+        }
+    }
+
+    // used by the `ber_tag!` macro
+    #[doc(hidden)]
+    #[must_use]
+    pub const fn __from_u64(v: u64) -> Self {
+        match Self::from_u64(v) {
+            Ok(tag) => tag,
+            Err(_) => panic!("invalid BER-TLV tag"),
+        }
     }
 
     pub(crate) fn read(r: &mut Reader) -> Result<Self> {
         let first = r.read_byte()?;
         let mut value = u64::from(first);
+        let mut len = 1;
         if first & Self::VALUE_MASK == Self::VALUE_MASK {
             loop {
-                value = value.checked_shl(8).ok_or(TlvError::InvalidInput)?;
                 let x = r.read_byte()?;
-                value |= u64::from(x);
-                if x & 0x80 == 0 {
+                len += 1;
+                if len <= Self::MAX_LEN {
+                    value = value << 8 | u64::from(x);
+                }
+                if x & Self::MORE_BYTES_MASK == 0 {
                     break;
                 }
             }
         }
-        let r = Self::try_from(value)?;
-        Ok(r)
+        if len > Self::MAX_LEN {
+            return Err(ErrorKind::TagIsRFU.into());
+        }
+        Self::try_from(value)
     }
 }
 
+/// Creates a constant BER-TLV [`Tag`] from an integer.
+///
+/// The tag encoding is checked at compile time: an invalid tag does not compile.
+///
+/// # Example
+/// ```rust
+/// use iso7816_tlv::{ber::Tag, ber_tag};
+///
+/// const LANGUAGE_PREFERENCE: Tag = ber_tag!(0x5F2D);
+/// assert_eq!(2, LANGUAGE_PREFERENCE.len_as_bytes());
+/// assert!(ber_tag!(0x7C).is_constructed());
+/// ```
+///
+/// ```compile_fail
+/// // second byte has its most significant bit set but is the last byte
+/// let tag = iso7816_tlv::ber_tag!(0x5F80);
+/// ```
+#[macro_export]
+macro_rules! ber_tag {
+    ($tag:expr) => {{
+        const TAG: $crate::ber::Tag = $crate::ber::Tag::__from_u64($tag);
+        TAG
+    }};
+}
+
 impl fmt::Display for Tag {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let t = [0, self.raw[0], self.raw[1], self.raw[2]];
-        let as_int: u32 = u32::from_be_bytes(t);
+        let as_int = u64::from_be_bytes(self.raw);
         write!(f, "Tag {:x} ({:?})", as_int, self.class())
     }
 }
 
 impl fmt::Debug for Tag {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let t = [0, self.raw[0], self.raw[1], self.raw[2]];
-        let as_int: u32 = u32::from_be_bytes(t);
+        let as_int = u64::from_be_bytes(self.raw);
         let constructed = if self.is_constructed() {
             "Contructed"
         } else {
@@ -279,48 +601,7 @@ impl Into<u64> for Tag {
 impl TryFrom<u64> for Tag {
     type Error = TlvError;
     fn try_from(v: u64) -> Result<Self> {
-        let bytes = v.to_be_bytes();
-        let mut first_non_zero = 0;
-        for &x in &bytes {
-            if x == 0 {
-                first_non_zero += 1;
-            } else {
-                break;
-            }
-        }
-        let raw = [
-            bytes[bytes.len() - 3],
-            bytes[bytes.len() - 2],
-            bytes[bytes.len() - 1],
-        ];
-        let bytes = &bytes[first_non_zero..];
-
-        let len = bytes.len();
-
-        match len {
-            0 => return Err(TlvError::InvalidInput),
-            1 => {
-                if (bytes[0] & Self::VALUE_MASK) == Self::VALUE_MASK {
-                    return Err(TlvError::InvalidInput);
-                }
-            }
-            2 => {
-                if (bytes[1] & Self::MORE_BYTES_MASK) == Self::MORE_BYTES_MASK {
-                    return Err(TlvError::InvalidInput);
-                }
-            }
-            3 => {
-                if (bytes[1] & Self::MORE_BYTES_MASK) == 0 {
-                    return Err(TlvError::InvalidInput);
-                }
-                if (bytes[2] & Self::MORE_BYTES_MASK) == Self::MORE_BYTES_MASK {
-                    return Err(TlvError::InvalidInput);
-                }
-            }
-            _ => return Err(TlvError::TagIsRFU),
-        }
-
-        Ok(Self { raw, len })
+        Self::from_u64(v)
     }
 }
 
@@ -384,6 +665,21 @@ impl TryFrom<&str> for Tag {
     }
 }
 
+/// Serialized as its hex encoding, such as `"9F4D"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Tag {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> core::result::Result<S::Ok, S::Error> {
+        crate::hex::Hex(self.to_bytes()).serialize(s)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Tag {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> core::result::Result<Self, D::Error> {
+        crate::hex::deserialize_tag(d)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
@@ -401,14 +697,33 @@ mod tests {
         assert!(Tag::try_from(0x7f_ff_22).is_ok());
         assert_eq!(0x7f_ff_22_u64, Tag::try_from(0x7f_ff_22)?.into());
 
-        assert_eq!(Err(TlvError::InvalidInput), Tag::try_from(0));
-        assert_eq!(Err(TlvError::InvalidInput), Tag::try_from(0x7f));
-        assert_eq!(Err(TlvError::InvalidInput), Tag::try_from(0x7f80));
-        assert_eq!(Err(TlvError::InvalidInput), Tag::try_from(0x7f_7f_00));
-        assert_eq!(Err(TlvError::TagIsRFU), Tag::try_from(0x7f_80_80_00));
+        assert_eq!(
+            Err(ErrorKind::InvalidInput),
+            Tag::try_from(0).map_err(|e: TlvError| e.kind())
+        );
+        assert_eq!(
+            Err(ErrorKind::InvalidInput),
+            Tag::try_from(0x7f).map_err(|e: TlvError| e.kind())
+        );
+        assert_eq!(
+            Err(ErrorKind::InvalidInput),
+            Tag::try_from(0x7f80).map_err(|e: TlvError| e.kind())
+        );
+        assert_eq!(
+            Err(ErrorKind::InvalidInput),
+            Tag::try_from(0x7f_7f_00).map_err(|e: TlvError| e.kind())
+        );
+        #[cfg(not(feature = "long-tags"))]
+        assert_eq!(
+            Err(ErrorKind::TagIsRFU),
+            Tag::try_from(0x7f_80_80_00).map_err(|e: TlvError| e.kind())
+        );
 
         assert!(Tag::try_from("7fff22").is_ok());
-        assert_eq!(Err(TlvError::ParseIntError), Tag::try_from("bad one"));
+        assert_eq!(
+            Err(ErrorKind::ParseIntError),
+            Tag::try_from("bad one").map_err(|e| e.kind())
+        );
 
         let t = Tag::try_from("5fff22")?;
         assert_eq!(3, t.len_as_bytes());
@@ -469,7 +784,121 @@ mod tests {
         let bad_vectors: [&[u8]; 2] = [&[0x7f, 0xff], &[0x7f, 0xff, 0xff]];
         for &v in &bad_vectors {
             let mut r = Reader::new(Input::from(v));
-            assert_eq!(Err(TlvError::TruncatedInput), Tag::read(&mut r));
+            assert_eq!(
+                Err(ErrorKind::TruncatedInput),
+                Tag::read(&mut r).map_err(|e| e.kind())
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "long-tags"))]
+    fn long_tag_rfu() {
+        let mut r = Reader::new(Input::from(&[0x7f, 0x81, 0x80, 0x01]));
+        assert_eq!(
+            Err(ErrorKind::TagIsRFU),
+            Tag::read(&mut r).map_err(|e| e.kind())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "long-tags")]
+    fn long_tag() -> Result<()> {
+        // tag number 0xFFFF_FFFF
+        let bytes = hex!("5F 8F FF FF FF 7F");
+        let tag = Tag::read(&mut Reader::new(Input::from(&bytes)))?;
+        assert_eq!(6, tag.len_as_bytes());
+        assert_eq!(&bytes[..], tag.to_bytes());
+        assert_eq!(Class::Application, tag.class());
+        assert!(!tag.is_constructed());
+        assert!(!tag.iso7816_compliant());
+        assert_eq!(tag, Tag::try_from(0x5F8F_FFFF_FF7F_u64)?);
+        assert_eq!(u32::MAX, tag.number());
+        assert_eq!(
+            tag,
+            Tag::from_parts(Class::Application, Form::Primitive, u32::MAX)?
+        );
+
+        let bytes = hex!("5F 9F FF FF FF 7F");
+        assert_eq!(
+            Err(ErrorKind::TagIsRFU),
+            Tag::read(&mut Reader::new(Input::from(&bytes))).map_err(|e| e.kind())
+        );
+        let bytes = hex!("3F 81 81 81 81 81 81 81 01");
+        assert_eq!(
+            Err(ErrorKind::TagIsRFU),
+            Tag::read(&mut Reader::new(Input::from(&bytes))).map_err(|e| e.kind())
+        );
+        assert_eq!(
+            Err(ErrorKind::InvalidInput),
+            Tag::try_from(0x9F_81_00_80_01_u64).map_err(|e| e.kind())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parts() -> Result<()> {
+        let vectors: [(u64, Class, Form, u32); 7] = [
+            (0x01, Class::Universal, Form::Primitive, 1),
+            (0x7C, Class::Application, Form::Constructed, 28),
+            (0x9F1F, Class::ContextSpecific, Form::Primitive, 31),
+            (0x5F2D, Class::Application, Form::Primitive, 45),
+            (0xBF0C, Class::ContextSpecific, Form::Constructed, 12),
+            (0xDF_81_00, Class::Private, Form::Primitive, 128),
+            (0x7F_FF_7F, Class::Application, Form::Constructed, 16383),
+        ];
+        for (v, class, form, number) in vectors {
+            let tag = Tag::try_from(v)?;
+            assert_eq!(class, tag.class());
+            assert_eq!(form, tag.form());
+            assert_eq!(number, tag.number(), "{v:x}");
+            // BF0C is not minimal
+            if v != 0xBF0C {
+                assert_eq!(tag, Tag::from_parts(class, form, number)?);
+            }
+            assert_eq!(v != 0xBF0C, tag.is_minimal());
+        }
+        assert_eq!(
+            Tag::try_from(0xAC)?,
+            Tag::from_parts(Class::ContextSpecific, true.into(), 12)?
+        );
+        // permissive non-minimal encodings
+        assert_eq!(0, Tag::try_from(0x7F00)?.number());
+
+        assert_eq!(0b1100_0000, u8::from(Class::Private));
+        assert_eq!(Form::Constructed, Form::from(0x7F));
+        assert_eq!(0x20, u8::from(Form::Constructed));
+
+        #[cfg(not(feature = "long-tags"))]
+        assert_eq!(
+            Err(ErrorKind::TagIsRFU),
+            Tag::from_parts(Class::Universal, Form::Primitive, 16384).map_err(|e| e.kind())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn const_tag() -> Result<()> {
+        const FCI: Tag = ber_tag!(0x6F);
+        const PROPRIETARY: Tag = ber_tag!(0xBF0C);
+        const BYTES: &[u8] = PROPRIETARY.to_bytes();
+        const CLASS: Class = PROPRIETARY.class();
+        const MINIMAL: [bool; 2] = [FCI.is_minimal(), ber_tag!(0x9F_1E).is_minimal()];
+        assert_eq!(Tag::try_from(0x6F)?, FCI);
+        assert_eq!([true, false], MINIMAL);
+        assert!(FCI.is_constructed());
+        assert_eq!(&[0xBF, 0x0C], BYTES);
+        assert_eq!(Class::ContextSpecific, CLASS);
+        assert_eq!(3, ber_tag!(0x7F_81_22).len_as_bytes());
+        #[cfg(feature = "long-tags")]
+        assert_eq!(6, ber_tag!(0x1F_8F_FF_FF_FF_7F).len_as_bytes());
+
+        for v in [0x00, 0x1F, 0x5F80, 0x5F_22_01, 0x7F_FF_FF_01] {
+            assert_eq!(
+                Tag::try_from(u64::from(v)).map_err(|e| e.kind()),
+                Tag::try_from_u32(v).map_err(|e| e.kind())
+            );
         }
         Ok(())
     }
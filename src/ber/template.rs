@@ -0,0 +1,321 @@
+//! Typed access to the children of constructed BER-TLV data objects
+//!
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use super::{Tag, Tlv, Value};
+use crate::{ErrorKind, Result, TlvError};
+
+/// Typed representation of the children of a constructed data object,
+/// usually implemented with `#[derive(BerTlv)]` (see the `derive` feature).
+///
+/// In [ISO7816-4], a template is a constructed data object
+/// whose value field consists of BER-TLV data objects.
+///
+/// # Example
+/// ```rust
+/// use iso7816_tlv::ber::{Tag, Template, Tlv, Value};
+/// use iso7816_tlv::{ber_tag, ErrorKind, TlvError};
+///
+/// const NAME: Tag = ber_tag!(0x5F20);
+///
+/// struct Name(Vec<u8>);
+///
+/// impl Template for Name {
+///     fn from_children(children: &[Tlv]) -> Result<Self, TlvError> {
+///         match children {
+///             [t] if t.tag() == &NAME => match t.value() {
+///                 Value::Primitive(v) => Ok(Self(v.clone())),
+///                 Value::Constructed(_) => Err(ErrorKind::Inconsistant.into()),
+///             },
+///             _ => Err(ErrorKind::InvalidInput.into()),
+///         }
+///     }
+///
+///     fn into_children(self) -> Vec<Tlv> {
+///         Tlv::new(NAME, Value::Primitive(self.0)).into_iter().collect()
+///     }
+/// }
+/// ```
+///
+/// [ISO7816-4]: https://www.iso.org/standard/54550.html
+pub trait Template: Sized {
+    /// Converts the children of a constructed data object.
+    /// # Errors
+    /// Fails if the children do not match the template.
+    fn from_children(children: &[Tlv]) -> Result<Self>;
+
+    /// Converts self into the children of a constructed data object.
+    fn into_children(self) -> Vec<Tlv>;
+}
+
+// used by `#[derive(BerTlv)]`
+#[doc(hidden)]
+pub mod __private {
+    use super::{ErrorKind, Result, Tag, Template, Tlv, TlvError, TryFrom, Value};
+
+    pub use alloc::vec::Vec;
+
+    /// Takes children by tag, checking that all of them are used.
+    pub struct Decoder<'a> {
+        children: &'a [Tlv],
+        used: Vec<bool>,
+    }
+
+    impl<'a> Decoder<'a> {
+        #[must_use]
+        pub fn new(children: &'a [Tlv]) -> Self {
+            Self {
+                children,
+                used: vec![false; children.len()],
+            }
+        }
+
+        fn take<'s>(&'s mut self, tag: &'s Tag) -> impl Iterator<Item = &'a Tlv> + 's {
+            let children = self.children;
+            self.used
+                .iter_mut()
+                .zip(children)
+                .filter(move |(used, t)| !**used && t.tag() == tag)
+                .map(|(used, t)| {
+                    *used = true;
+                    t
+                })
+        }
+
+        /// First unused primitive child with the given tag.
+        /// # Errors
+        /// Fails if the child is constructed, or if its value cannot be converted.
+        pub fn primitive<T: Primitive>(&mut self, tag: &Tag) -> Result<Option<T>> {
+            self.take(tag).next().map(primitive).transpose()
+        }
+
+        /// All unused primitive children with the given tag.
+        /// # Errors
+        /// see [`primitive()`](Self::primitive())
+        pub fn primitives<T: Primitive>(&mut self, tag: &Tag) -> Result<Vec<T>> {
+            self.take(tag).map(primitive).collect()
+        }
+
+        /// First unused constructed child with the given tag.
+        /// # Errors
+        /// Fails if the child is primitive, or does not match the template.
+        pub fn constructed<T: Template>(&mut self, tag: &Tag) -> Result<Option<T>> {
+            self.take(tag).next().map(constructed).transpose()
+        }
+
+        /// All unused constructed children with the given tag.
+        /// # Errors
+        /// see [`constructed()`](Self::constructed())
+        pub fn constructeds<T: Template>(&mut self, tag: &Tag) -> Result<Vec<T>> {
+            self.take(tag).map(constructed).collect()
+        }
+
+        /// Checks that all children were used.
+        /// # Errors
+        /// Fails with [`ErrorKind::UnexpectedField`] for the first unused child.
+        pub fn finish(self) -> Result<()> {
+            match self
+                .used
+                .iter()
+                .zip(self.children)
+                .find(|(used, _)| !**used)
+            {
+                Some((_, t)) => Err(error(ErrorKind::UnexpectedField, t.tag())),
+                None => Ok(()),
+            }
+        }
+    }
+
+    /// Decodes the children of `tlv`, which must have the given tag.
+    /// # Errors
+    /// Fails if the tag does not match, or if `tlv` is primitive.
+    pub fn from_tlv<T: Template>(tlv: &Tlv, tag: &Tag) -> Result<T> {
+        if tlv.tag() != tag {
+            return Err(error(ErrorKind::InvalidInput, tlv.tag()));
+        }
+        constructed(tlv)
+    }
+
+    /// Encodes `value` as a constructed data object with the given tag.
+    pub fn into_tlv<T: Template>(value: T, tag: &Tag) -> Tlv {
+        tlv(tag, Value::Constructed(value.into_children()))
+    }
+
+    /// Mandatory child.
+    /// # Errors
+    /// Fails with [`ErrorKind::MissingField`] if the child is missing.
+    pub fn required<T>(value: Option<T>, tag: &Tag) -> Result<T> {
+        value.ok_or_else(|| error(ErrorKind::MissingField, tag))
+    }
+
+    fn error(kind: ErrorKind, tag: &Tag) -> TlvError {
+        TlvError::new(kind).with_tag(tag)
+    }
+
+    /// Value of a primitive child.
+    pub trait Primitive: Sized {
+        /// # Errors
+        /// Fails if the value cannot be converted.
+        fn from_value(value: &[u8]) -> Result<Self>;
+    }
+
+    impl<T> Primitive for T
+    where
+        T: for<'v> TryFrom<&'v [u8]>,
+        TlvError: for<'v> From<<T as TryFrom<&'v [u8]>>::Error>,
+    {
+        fn from_value(value: &[u8]) -> Result<Self> {
+            Ok(T::try_from(value)?)
+        }
+    }
+
+    fn primitive<T: Primitive>(tlv: &Tlv) -> Result<T> {
+        match tlv.value() {
+            Value::Primitive(v) => T::from_value(v).map_err(|e| e.with_tag(tlv.tag())),
+            Value::Constructed(_) => Err(error(ErrorKind::Inconsistant, tlv.tag())),
+        }
+    }
+
+    // tags are checked against values at compile time by the derive macro
+    fn tlv(tag: &Tag, value: Value) -> Tlv {
+        Tlv::new(tag.clone(), value)
+            .unwrap_or_else(|_| unreachable!("BER-TLV tag does not match the value"))
+    }
+
+    fn constructed<T: Template>(tlv: &Tlv) -> Result<T> {
+        match tlv.value() {
+            Value::Constructed(children) => {
+                T::from_children(children).map_err(|e| e.with_tag(tlv.tag()))
+            }
+            Value::Primitive(_) => Err(error(ErrorKind::Inconsistant, tlv.tag())),
+        }
+    }
+
+    /// Collects children.
+    #[derive(Default)]
+    pub struct Encoder {
+        children: Vec<Tlv>,
+    }
+
+    impl Encoder {
+        pub fn primitive<T: Into<Vec<u8>>>(&mut self, tag: &Tag, value: T) {
+            self.push(tag, Value::Primitive(value.into()));
+        }
+
+        pub fn constructed<T: Template>(&mut self, tag: &Tag, value: T) {
+            self.push(tag, Value::Constructed(value.into_children()));
+        }
+
+        fn push(&mut self, tag: &Tag, value: Value) {
+            self.children.push(tlv(tag, value));
+        }
+
+        #[must_use]
+        pub fn finish(self) -> Vec<Tlv> {
+            self.children
+        }
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+    use crate::ber::BerTlv;
+    use crate::ber_tag;
+
+    #[derive(BerTlv, Debug, PartialEq)]
+    struct Proprietary {
+        #[tlv(tag = 0x88)]
+        sfi: [u8; 1],
+        #[tlv(tag = 0x5F2D, optional)]
+        language: Option<Vec<u8>>,
+        #[tlv(constructed = 0xBF0C, optional)]
+        discretionary: Option<Discretionary>,
+    }
+
+    #[derive(BerTlv, Debug, PartialEq)]
+    struct Discretionary {
+        #[tlv(tag = 0x9F4D, repeated)]
+        log_entries: Vec<Vec<u8>>,
+    }
+
+    #[derive(BerTlv, Debug, PartialEq)]
+    #[tlv(tag = 0x6F)]
+    struct Fci {
+        #[tlv(tag = 0x84)]
+        name: Vec<u8>,
+        #[tlv(constructed = 0xA5)]
+        proprietary: Proprietary,
+    }
+
+    #[test]
+    fn derive() -> Result<()> {
+        let input = hex!("6F 17 84 02 A000 A5 11 88 01 02 BF0C 08 9F4D 02 0B0A 9F4D 00 5F2D 00");
+        let tlv = Tlv::from_bytes(&input)?;
+        let fci = Fci::try_from(&tlv)?;
+        let expected = Fci {
+            name: vec![0xA0, 0x00],
+            proprietary: Proprietary {
+                sfi: [0x02],
+                language: Some(vec![]),
+                discretionary: Some(Discretionary {
+                    log_entries: vec![vec![0x0B, 0x0A], vec![]],
+                }),
+            },
+        };
+        assert_eq!(expected, fci);
+
+        // children are written in field order
+        let expected = hex!("6F 17 84 02 A000 A5 11 88 01 02 5F2D 00 BF0C 08 9F4D 02 0B0A 9F4D 00");
+        assert_eq!(expected.to_vec(), Tlv::from(fci).to_vec());
+
+        let tlv = Tlv::from_bytes(&hex!("6F 07 84 00 A5 03 88 01 02"))?;
+        let fci = Fci::try_from(&tlv)?;
+        assert_eq!(None, fci.proprietary.language);
+        assert_eq!(None, fci.proprietary.discretionary);
+        assert_eq!(tlv, Tlv::from(fci));
+        Ok(())
+    }
+
+    #[test]
+    fn errors() -> Result<()> {
+        // missing mandatory data object
+        let tlv = Tlv::from_bytes(&hex!("6F 05 A5 03 88 01 02"))?;
+        let e = Fci::try_from(&tlv).unwrap_err();
+        assert_eq!(ErrorKind::MissingField, e.kind());
+        assert_eq!(Some(&ber_tag!(0x84)), e.tag());
+
+        // nested missing data object
+        let tlv = Tlv::from_bytes(&hex!("6F 07 84 00 A5 03 5F2D 00"))?;
+        let e = Fci::try_from(&tlv).unwrap_err();
+        assert_eq!(ErrorKind::MissingField, e.kind());
+        assert_eq!(Some(&ber_tag!(0x88)), e.tag());
+
+        // unexpected data object
+        let tlv = Tlv::from_bytes(&hex!("6F 09 84 00 A5 03 88 01 02 50 00"))?;
+        let e = Fci::try_from(&tlv).unwrap_err();
+        assert_eq!(ErrorKind::UnexpectedField, e.kind());
+        assert_eq!(Some(&ber_tag!(0x50)), e.tag());
+
+        // duplicate non repeated data object
+        let tlv = Tlv::from_bytes(&hex!("6F 09 84 00 84 00 A5 03 88 01 02"))?;
+        let e = Fci::try_from(&tlv).unwrap_err();
+        assert_eq!(ErrorKind::UnexpectedField, e.kind());
+        assert_eq!(Some(&ber_tag!(0x84)), e.tag());
+
+        // wrong tag
+        let tlv = Tlv::from_bytes(&hex!("70 07 84 00 A5 03 88 01 02"))?;
+        let e = Fci::try_from(&tlv).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, e.kind());
+        assert_eq!(Some(&ber_tag!(0x70)), e.tag());
+
+        // invalid value length
+        let tlv = Tlv::from_bytes(&hex!("6F 08 84 00 A5 04 88 02 0102"))?;
+        let e = Fci::try_from(&tlv).unwrap_err();
+        assert_eq!(ErrorKind::InvalidLength, e.kind());
+        assert_eq!(Some(&ber_tag!(0x88)), e.tag());
+        Ok(())
+    }
+}
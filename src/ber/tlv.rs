@@ -1,10 +1,16 @@
+use alloc::boxed::Box;
 use alloc::vec::Vec;
 use core::fmt;
+use core::ops::ControlFlow;
 
-use untrusted::{Input, Reader};
-
-use super::{Tag, Value};
-use crate::{Result, TlvError};
+use super::length::{decode_len, encode_len};
+use super::tlv_ref::padding_len;
+#[cfg(feature = "std")]
+use super::{Decoded, Decoder};
+use super::{LengthForm, Match, Padding, ParseOptions, Path, Tag, TlvIter, TlvRef, Value};
+#[cfg(feature = "std")]
+use crate::IoSink;
+use crate::{ErrorKind, Result, Sink};
 
 /// BER-TLV structure, following ISO/IEC 7816-4.
 /// > # BER-TLV data objects
@@ -18,32 +24,71 @@ use crate::{Result, TlvError};
 /// >   It encodes a length, i.e., a number denoted N.
 /// > - If N is zero, there is no value field, i.e., the data object is empty.
 /// >   Otherwise (N > 0), the value field consists of N consecutive bytes.
-#[derive(PartialEq, Debug, Clone)]
+///
+/// Data objects are equal when their tags and values are equal:
+/// the encoding details kept when parsing (length forms, length fields and padding)
+/// are not compared.
+#[derive(Debug, Clone)]
 pub struct Tlv {
     tag: Tag,
     value: Value,
+    length_form: LengthForm,
+    // set when the parsed length field was not encoded on the minimal number of bytes
+    long_len: bool,
+    // parsed encoding kept on request, boxed as seldom used to keep `Tlv` small
+    encoding: Option<Box<Encoding>>,
+}
+
+// encoding details kept when parsing, see `to_vec_original()`
+#[derive(Debug, Clone, Default)]
+struct Encoding {
+    // padding bytes preserved when parsing a constructed value,
+    // along with the index of the child they precede
+    padding: Vec<(usize, Vec<u8>)>,
+    // padding bytes preserved before and after a top-level data object,
+    // only written when serializing self, not as a child of another data object
+    outer_padding: [Vec<u8>; 2],
+    // length field found when parsing, if not the minimal definite form one
+    // and `ParseOptions::original_lengths()` is set
+    raw_len: Option<Vec<u8>>,
+}
+
+impl Encoding {
+    // whether the encoding of the data object is the one given by `Tlv::to_vec()`
+    fn is_empty(&self) -> bool {
+        self.raw_len.is_none()
+            && self.padding.is_empty()
+            && self.outer_padding.iter().all(Vec::is_empty)
+    }
 }
 
 impl Tlv {
-    /// Create a BER-TLV data object from valid tag and value.alloc
+    /// Create a BER-TLV data object from valid tag and value.
+    /// The length field will be encoded in definite form.
     /// # Errors
-    /// Fails with [`TlvError::Inconsistant`]
+    /// Fails with [`ErrorKind::Inconsistant`]
     /// if the tag indicates a contructed value (resp. primitive) and the
     /// value is primitive (resp. contructed).
     pub fn new(tag: Tag, value: Value) -> Result<Self> {
         match value {
             Value::Constructed(_) => {
                 if !tag.is_constructed() {
-                    return Err(TlvError::Inconsistant);
+                    return Err(ErrorKind::Inconsistant.into());
                 }
             }
             Value::Primitive(_) => {
                 if tag.is_constructed() {
-                    return Err(TlvError::Inconsistant);
+                    return Err(ErrorKind::Inconsistant.into());
                 }
             }
         }
-        Ok(Self { tag, value })
+        Ok(Self {
+            tag,
+            value,
+            length_form: LengthForm::Definite,
+            long_len: false,
+            encoding: None,
+        })
     }
 
     /// Get BER-TLV  tag.
@@ -52,10 +97,11 @@ impl Tlv {
         &self.tag
     }
 
-    /// Get BER-TLV value length
+    /// Get BER-TLV length, tag and length fields included,
+    /// same as [`encoded_len()`](Self::encoded_len()).
     #[must_use]
     pub fn length(&self) -> usize {
-        self.len()
+        self.encoded_len()
     }
 
     /// Get BER-TLV value
@@ -64,116 +110,436 @@ impl Tlv {
         &self.value
     }
 
-    fn len_length(l: usize) -> usize {
-        match l {
-            0..=127 => 1,
-            128..=255 => 2,
-            256..=65_535 => 3,
-            65_536..=16_777_215 => 4,
-            _ => 5,
+    /// Get the form used to encode the length field.
+    /// Parsed data objects keep the form found in the input.
+    #[must_use]
+    pub fn length_form(&self) -> LengthForm {
+        self.length_form
+    }
+
+    /// Set the form used to encode the length field.
+    /// # Errors
+    /// Fails with [`ErrorKind::Inconsistant`] when setting the
+    /// [`Indefinite`](LengthForm::Indefinite) form on a primitive data object.
+    pub fn set_length_form(&mut self, length_form: LengthForm) -> Result<()> {
+        if length_form == LengthForm::Indefinite && !self.value.is_constructed() {
+            return Err(ErrorKind::Inconsistant.into());
+        }
+        self.length_form = length_form;
+        Ok(())
+    }
+
+    /// Removes the padding bytes preserved when parsing,
+    /// from self and all nested data objects.
+    /// See [`Padding::Preserve`].
+    pub fn remove_padding(&mut self) {
+        if let Some(encoding) = &mut self.encoding {
+            encoding.padding.clear();
+            encoding.outer_padding = Default::default();
+        }
+        if let Value::Constructed(children) = &mut self.value {
+            for c in children {
+                c.remove_padding();
+            }
         }
     }
 
-    #[allow(clippy::cast_possible_truncation)]
-    fn inner_len_to_vec(&self) -> Vec<u8> {
-        let l = self.value.len_as_bytes();
-        if l < 0x7f {
-            vec![l as u8]
-        } else {
-            let mut ret: Vec<u8> = l
-                .to_be_bytes()
+    /// Whether self was encoded following the Distinguished Encoding Rules (DER)
+    /// when parsed, see [`TlvRef::is_der()`].
+    /// Data objects created with [`new()`](Self::new()) are DER encoded
+    /// unless their tag is not minimal, or they use the indefinite length form.
+    #[must_use]
+    pub fn is_der(&self) -> bool {
+        self.length_form == LengthForm::Definite
+            && self.tag.is_minimal()
+            && !self.long_len
+            && self.encoding.iter().all(|e| e.is_empty())
+            && match &self.value {
+                Value::Primitive(_) => true,
+                Value::Constructed(children) => children.iter().all(Self::is_der),
+            }
+    }
+
+    // length of the value field, including preserved padding,
+    // each nested data object being visited once
+    // value lengths of self and nested data objects are recorded in encoding order
+    // into `lens` if given, see `encode_with()`
+    // `original` selects the length fields found when parsing, see `to_vec_original()`
+    fn value_len(&self, original: bool, mut lens: Option<&mut Vec<usize>>) -> usize {
+        let index = lens.as_deref_mut().map(|lens| {
+            lens.push(0);
+            lens.len() - 1
+        });
+        let padding: usize = self.padding().iter().map(|(_, p)| p.len()).sum();
+        let inner_len = match &self.value {
+            Value::Primitive(v) => v.len(),
+            Value::Constructed(children) => children
                 .iter()
-                .skip_while(|&x| *x == 0)
-                .copied()
-                .collect();
-            ret.insert(0, 0x80 | ret.len() as u8);
-            ret
+                .map(|c| c.encoded_len_with(original, lens.as_deref_mut()))
+                .sum(),
+        };
+        let len = inner_len + padding;
+        if let (Some(lens), Some(index)) = (lens, index) {
+            lens[index] = len;
         }
+        len
     }
 
-    pub(crate) fn len(&self) -> usize {
-        let inner_len = self.value.len_as_bytes();
-        self.tag.len_as_bytes() + Self::len_length(inner_len) + inner_len
+    // definite form length field for a value field of `len` bytes
+    fn length_field<'a>(&'a self, len: usize, original: bool, buf: &'a mut [u8; 9]) -> &'a [u8] {
+        match self.encoding.as_deref().and_then(|e| e.raw_len.as_deref()) {
+            // the parsed field is only kept while it matches the value
+            Some(raw) if original && decode_len(raw) == Some(len) => raw,
+            _ => encode_len(len, buf),
+        }
     }
 
-    /// serializes self into a byte vector.
+    // padding preserved between children, along with the index of the child they precede
+    fn padding(&self) -> &[(usize, Vec<u8>)] {
+        self.encoding.as_deref().map_or(&[], |e| &e.padding)
+    }
+
+    // length of the tag and length fields, and of the end-of-contents if any
+    fn header_len(&self, len: usize, original: bool) -> usize {
+        match self.length_form {
+            LengthForm::Definite => {
+                let mut buf = [0; 9];
+                self.tag.len_as_bytes() + self.length_field(len, original, &mut buf).len()
+            }
+            // '80' and end-of-contents '00 00'
+            LengthForm::Indefinite => self.tag.len_as_bytes() + 1 + 2,
+        }
+    }
+
+    pub(super) fn encoded_len_with(&self, original: bool, lens: Option<&mut Vec<usize>>) -> usize {
+        let len = self.value_len(original, lens);
+        self.header_len(len, original) + len
+    }
+
+    /// Converts self and all nested data objects to the definite length form.
+    pub fn make_definite(&mut self) {
+        self.length_form = LengthForm::Definite;
+        if let Value::Constructed(children) = &mut self.value {
+            for c in children {
+                c.make_definite();
+            }
+        }
+    }
+
+    /// Length of self once serialized into BER-TLV data.
+    /// This does not allocate.
     #[must_use]
-    pub fn to_vec(&self) -> Vec<u8> {
-        let mut ret: Vec<u8> = Vec::new();
-        ret.extend(self.tag.to_bytes().iter());
-        ret.append(&mut self.inner_len_to_vec());
+    pub fn encoded_len(&self) -> usize {
+        self.top_level_len(false)
+    }
+
+    // encoded length, including the padding preserved around a top-level data object
+    fn top_level_len(&self, original: bool) -> usize {
+        let [before, after] = self.outer_padding();
+        before.len() + self.encoded_len_with(original, None) + after.len()
+    }
+
+    // streaming encoding routine, output bytes are given to `write` in order
+    // `lens` yields the value lengths recorded by `value_len()`,
+    // so that they are not computed again for each length field
+    fn encode_with<F>(
+        &self,
+        original: bool,
+        lens: &mut impl Iterator<Item = usize>,
+        write: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(&[u8]) -> Result<()>,
+    {
+        // recorded for each data object, unless `lens` does not match self
+        let len = lens.next().ok_or(ErrorKind::Inconsistant)?;
+        write(self.tag.to_bytes())?;
+        match self.length_form {
+            LengthForm::Definite => {
+                let mut buf = [0; 9];
+                write(self.length_field(len, original, &mut buf))?;
+            }
+            LengthForm::Indefinite => write(&[0x80])?,
+        }
         match &self.value {
-            Value::Primitive(v) => ret.extend(v.iter()),
+            Value::Primitive(v) => write(v)?,
             Value::Constructed(tlv) => {
-                for t in tlv {
-                    ret.append(&mut t.to_vec());
+                let mut padding = self.padding().iter().peekable();
+                for (i, t) in tlv.iter().enumerate() {
+                    while let Some((_, p)) = padding.next_if(|(index, _)| *index <= i) {
+                        write(p)?;
+                    }
+                    t.encode_with(original, lens, write)?;
+                }
+                for (_, p) in padding {
+                    write(p)?;
                 }
             }
-        };
-        ret
+        }
+        if self.length_form == LengthForm::Indefinite {
+            write(&[0, 0])?;
+        }
+        Ok(())
     }
 
-    fn read_len(r: &mut Reader) -> Result<usize> {
-        let mut ret: usize = 0;
-        let x = r.read_byte()?;
-        if x & 0x80 == 0 {
-            ret = x as usize;
-        } else {
-            let n_bytes = x as usize & 0x7f;
-            if n_bytes > 4 {
-                return Err(TlvError::InvalidLength);
+    // buffer encoding routine, writing self just before `out[end]`, from the end:
+    // the length of a value field is known once it is written, without being computed beforehand
+    // returns the index of the first written byte
+    fn encode_back(&self, original: bool, out: &mut [u8], mut end: usize) -> usize {
+        fn put(out: &mut [u8], end: &mut usize, bytes: &[u8]) {
+            let start = *end - bytes.len();
+            out[start..*end].copy_from_slice(bytes);
+            *end = start;
+        }
+
+        if self.length_form == LengthForm::Indefinite {
+            put(out, &mut end, &[0, 0]);
+        }
+        let value_end = end;
+        match &self.value {
+            Value::Primitive(v) => put(out, &mut end, v),
+            Value::Constructed(tlv) => {
+                let mut padding = self.padding().iter().rev().peekable();
+                for (i, t) in tlv.iter().enumerate().rev() {
+                    while let Some((_, p)) = padding.next_if(|(index, _)| *index > i) {
+                        put(out, &mut end, p);
+                    }
+                    end = t.encode_back(original, out, end);
+                }
+                for (_, p) in padding {
+                    put(out, &mut end, p);
+                }
             }
-            for _ in 0..n_bytes {
-                let x = r.read_byte()?;
-                ret = ret << 8 | x as usize;
+        }
+        match self.length_form {
+            LengthForm::Definite => {
+                let mut buf = [0; 9];
+                let len = value_end - end;
+                put(out, &mut end, self.length_field(len, original, &mut buf));
             }
+            LengthForm::Indefinite => put(out, &mut end, &[0x80]),
+        }
+        put(out, &mut end, self.tag.to_bytes());
+        end
+    }
+
+    /// serializes self into a byte vector.
+    /// Length fields are encoded using the form of each data object,
+    /// see [`make_definite()`](Self::make_definite()) to get a definite length encoding.
+    #[must_use]
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.to_vec_with(false)
+    }
+
+    /// serializes self into a byte vector, reproducing the parsed encoding.
+    ///
+    /// Contrary to [`to_vec()`](Self::to_vec()), length fields that were not encoded
+    /// on the minimal number of bytes keep their parsed encoding when parsed with
+    /// [`ParseOptions::original_lengths()`], as long as the value length is unchanged.
+    /// As tags, length forms and preserved padding (see [`Padding::Preserve`])
+    /// are also kept, this gives back the exact parsed bytes, for instance to check
+    /// a signature computed over them.
+    /// The encoding of a data object parsed with [`TlvRef`] is also available
+    /// without copy, see [`TlvRef::as_bytes()`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::convert::TryFrom;
+    /// use iso7816_tlv::ber::{ParseOptions, Tag, Tlv};
+    /// # use iso7816_tlv::{ErrorKind, TlvError};
+    ///
+    /// # fn main() -> Result<(), TlvError> {
+    /// let input = [0x70, 0x81, 0x04, 0x5A, 0x81, 0x01, 0x00];
+    /// let options = ParseOptions::new().original_lengths(true);
+    /// let tlv = Tlv::parse_with(&input, &options).0?;
+    /// assert_eq!(vec![0x70, 0x03, 0x5A, 0x01, 0x00], tlv.to_vec());
+    /// assert_eq!(input.to_vec(), tlv.to_vec_original());
+    ///
+    /// // nested data objects keep their encoding as well
+    /// let pan = tlv.find(&Tag::try_from(0x5A)?).ok_or(ErrorKind::InvalidInput)?;
+    /// assert_eq!(&input[3..], &pan.to_vec_original()[..]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn to_vec_original(&self) -> Vec<u8> {
+        self.to_vec_with(true)
+    }
+
+    fn to_vec_with(&self, original: bool) -> Vec<u8> {
+        let mut ret = vec![0; self.top_level_len(original)];
+        self.encode_top_level(original, &mut ret);
+        ret
+    }
+
+    // padding preserved before and after a top-level data object
+    fn outer_padding(&self) -> [&[u8]; 2] {
+        match self.encoding.as_deref() {
+            Some(e) => [&e.outer_padding[0], &e.outer_padding[1]],
+            None => [&[], &[]],
         }
-        Ok(ret)
     }
 
-    fn read(r: &mut Reader) -> Result<Self> {
-        let tag = Tag::read(r)?;
-        let len = Self::read_len(r)?;
+    // sets the padding preserved before (0) or after (1) a top-level data object
+    pub(super) fn set_outer_padding(&mut self, index: usize, padding: &[u8]) {
+        if !padding.is_empty() {
+            self.encoding.get_or_insert_with(Box::default).outer_padding[index] = padding.to_vec();
+        }
+    }
+
+    // writes self into `out`, of the length given by `top_level_len()`
+    fn encode_top_level(&self, original: bool, out: &mut [u8]) {
+        let [before, after] = self.outer_padding();
+        let (start, out) = out.split_at_mut(before.len());
+        start.copy_from_slice(before);
+        let end = out.len() - after.len();
+        out[end..].copy_from_slice(after);
+        self.encode_back(original, out, end);
+    }
+
+    /// serializes self into the beginning of `buf`, without allocating.
+    /// Returns the number of bytes written, see also [`encoded_len()`](Self::encoded_len()).
+    /// # Errors
+    /// Fails with [`ErrorKind::BufferTooSmall`] if `buf` cannot hold the encoded data object.
+    pub fn encode_into(&self, buf: &mut [u8]) -> Result<usize> {
+        let len = self.encoded_len();
+        let out = buf.get_mut(..len).ok_or(ErrorKind::BufferTooSmall)?;
+        self.encode_top_level(false, out);
+        Ok(len)
+    }
+
+    /// serializes self into a byte [`Sink`], without copying the encoded data.
+    /// Only the value lengths are computed beforehand.
+    /// # Errors
+    /// Fails if the sink fails, see [`Sink::write_all()`].
+    pub fn encode_to<S: Sink + ?Sized>(&self, sink: &mut S) -> Result<()> {
+        let mut lens = Vec::new();
+        self.value_len(false, Some(&mut lens));
+        let [before, after] = self.outer_padding();
+        sink.write_all(before)?;
+        self.encode_with(false, &mut lens.into_iter(), &mut |b| sink.write_all(b))?;
+        sink.write_all(after)
+    }
+
+    /// serializes self into an [`std::io::Write`].
+    /// # Errors
+    /// Fails with [`ErrorKind::WriteError`] if the writer fails.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write + ?Sized>(&self, w: &mut W) -> Result<()> {
+        self.encode_to(&mut IoSink::new(w))
+    }
+
+    /// Reads a BER-TLV data object from an [`std::io::Read`].
+    /// Bytes following the data object are not consumed,
+    /// so that a stream of data objects can be read by repeated calls.
+    /// # Errors
+    /// Fails with [`ErrorKind::TruncatedInput`] if the end of the reader is reached
+    /// before the end of the data object, with [`ErrorKind::ReadError`] if the reader fails,
+    /// or if read data is not valid BER-TLV.
+    #[cfg(feature = "std")]
+    pub fn read_from<R: std::io::Read + ?Sized>(r: &mut R) -> Result<Self> {
+        Self::read_from_with(r, &ParseOptions::new())
+    }
 
-        let ret = if tag.is_constructed() {
-            let mut val = Value::Constructed(vec![]);
-            while val.len_as_bytes() < len {
-                let tlv = Self::read(r)?;
-                val.push(tlv)?;
+    /// Reads a BER-TLV data object from an [`std::io::Read`], with given options.
+    /// Limits are checked while reading, see [`Decoder::with_options()`].
+    /// # Errors
+    /// see [`read_from()`](Self::read_from())
+    #[cfg(feature = "std")]
+    pub fn read_from_with<R: std::io::Read + ?Sized>(
+        r: &mut R,
+        options: &ParseOptions,
+    ) -> Result<Self> {
+        // claimed lengths are not trusted, data is read by chunks
+        let mut chunk = [0_u8; 256];
+        let mut decoder = Decoder::with_options(*options);
+        loop {
+            match decoder.decode()? {
+                Decoded::Tlv(tlv) => return Ok(tlv),
+                Decoded::NeedMore(n) => {
+                    let chunk = &mut chunk[..n.min(256)];
+                    r.read_exact(chunk)?;
+                    decoder.push(chunk);
+                }
             }
-            Self::new(tag, val)?
-        } else {
-            let content = r.read_bytes(len)?;
-            Self::new(tag, Value::Primitive(content.as_slice_less_safe().to_vec()))?
-        };
-        if ret.value.len_as_bytes() == len {
-            Ok(ret)
-        } else {
-            Err(TlvError::Inconsistant)
         }
     }
 
     /// Parses a byte array into a BER-TLV structure.
     /// This also returns the unprocessed data.
     pub fn parse(input: &[u8]) -> (Result<Self>, &[u8]) {
-        let mut r = Reader::new(Input::from(input));
-        (
-            Self::read(&mut r),
-            r.read_bytes_to_end().as_slice_less_safe(),
-        )
+        Self::parse_with(input, &ParseOptions::new())
+    }
+
+    /// Parses a byte array into a BER-TLV structure, with given options.
+    /// This also returns the unprocessed data.
+    /// See [`TlvRef::parse_with()`].
+    pub fn parse_with<'a>(input: &'a [u8], options: &ParseOptions) -> (Result<Self>, &'a [u8]) {
+        let (r, rest) = TlvRef::parse_with(input, options);
+        let r = r.map(|t| {
+            let mut tlv = Self::from(&t);
+            if options.padding == Padding::Preserve {
+                let start = padding_len(input);
+                let end = start + t.as_bytes().len();
+                tlv.set_outer_padding(0, &input[..start]);
+                tlv.set_outer_padding(1, &input[end..input.len() - rest.len()]);
+            }
+            tlv
+        });
+        (r, rest)
+    }
+
+    /// Parses a byte array into a BER-TLV structure,
+    /// only accepting the Distinguished Encoding Rules (DER), see [`ParseOptions::der()`].
+    /// This also returns the unprocessed data.
+    ///
+    /// # Example
+    /// ```rust
+    /// use iso7816_tlv::ber::Tlv;
+    /// use iso7816_tlv::ErrorKind;
+    ///
+    /// let (tlv, _) = Tlv::parse_der(&[0x5A, 0x81, 0x01, 0x00]);
+    /// assert_eq!(Err(ErrorKind::InvalidLength), tlv.map_err(|e| e.kind()));
+    /// ```
+    pub fn parse_der(input: &[u8]) -> (Result<Self>, &[u8]) {
+        Self::parse_with(input, &ParseOptions::der())
     }
 
     /// Parses a byte array into a vector of BER-TLV.
+    /// `00` and `FF` padding bytes before, between and after data objects are skipped.
     /// # Note
     /// Errors are discarded and parsing stops at first error
-    /// Prefer using the [`parse()`](Self::parse()) method and iterate over returned processed data.
+    /// Prefer using [`TlvIter`] to get errors and resume parsing.
     #[must_use]
     pub fn parse_all(input: &[u8]) -> Vec<Self> {
-        let mut ret = Vec::new();
-        let mut r = Reader::new(Input::from(input));
-        while !r.at_end() {
-            if Self::read(&mut r).map(|elem| ret.push(elem)).is_err() {
-                break;
+        Self::parse_all_with(input, &ParseOptions::new())
+    }
+
+    /// Parses a byte array into a vector of BER-TLV, with given options.
+    /// # Note
+    /// Errors are discarded and parsing stops at first error
+    #[must_use]
+    pub fn parse_all_with(input: &[u8], options: &ParseOptions) -> Vec<Self> {
+        let mut iter = TlvIter::with_options(input, *options);
+        let mut ret: Vec<Self> = Vec::new();
+        // end of the last parsed data object
+        let mut end = 0;
+        while let Some(Ok(t)) = iter.next() {
+            let mut tlv = Self::from(&t);
+            if options.padding == Padding::Preserve {
+                // padding is attached to the preceding data object, if any
+                let padding = &input[end..iter.offset() - t.as_bytes().len()];
+                match ret.last_mut() {
+                    Some(last) => last.set_outer_padding(1, padding),
+                    None => tlv.set_outer_padding(0, padding),
+                }
+                end = iter.offset();
+            }
+            ret.push(tlv);
+        }
+        if let Some(last) = ret.last_mut() {
+            if options.padding == Padding::Preserve {
+                last.set_outer_padding(1, &input[end..end + padding_len(&input[end..])]);
             }
         }
         ret
@@ -182,66 +548,367 @@ impl Tlv {
     /// Parses a byte array into a BER-TLV structure.
     /// Input must exactly match a BER-TLV object.
     /// # Errors
-    /// Fails with [`TlvError::InvalidInput`] if input does not match a BER-TLV object.
+    /// Fails if input is not valid BER-TLV,
+    /// or with [`ErrorKind::InvalidInput`] if data remains after the BER-TLV object.
     pub fn from_bytes(input: &[u8]) -> Result<Self> {
         let (r, n) = Self::parse(input);
+        let tlv = r?;
         if n.is_empty() {
-            r
+            Ok(tlv)
         } else {
-            Err(TlvError::InvalidInput)
+            Err(ErrorKind::InvalidInput.into())
         }
     }
 
-    /// Finds first occurence of a TLV object with given tag in self.
+    /// Finds the first data object with given tag in self, in document order.
+    /// Self and its descendants are searched, constructed or primitive.
     #[must_use]
     pub fn find(&self, tag: &Tag) -> Option<&Self> {
-        match &self.value {
-            Value::Primitive(_) => {
-                if self.tag == *tag {
-                    Some(self)
-                } else {
-                    None
-                }
-            }
-            Value::Constructed(e) => {
-                for x in e {
-                    match x.find(tag) {
-                        None => (),
-                        Some(e) => return Some(e),
-                    }
-                }
-                None
-            }
-        }
+        self.find_by(|x| x.tag == *tag)
     }
 
-    /// find all occurences of TLV objects with given given tag in self.
+    /// Finds all data objects with given tag in self, in document order.
+    /// Self and its descendants are searched, constructed or primitive.
     /// Note that searching [`ContextSpecific`](crate::ber::tag::Class::ContextSpecific) class tag (0x80 for instance) will return
     /// a vector of possibly unrelated tlv data.
     #[must_use]
     pub fn find_all(&self, tag: &Tag) -> Vec<&Self> {
-        let mut ret: Vec<&Self> = Vec::new();
+        self.find_all_by(|x| x.tag == *tag)
+    }
+
+    /// Finds the first data object of self and its descendants, in document order,
+    /// for which `predicate` returns `true`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use iso7816_tlv::ber::Tlv;
+    /// # use iso7816_tlv::TlvError;
+    ///
+    /// # fn main() -> Result<(), TlvError> {
+    /// let data = [0x6F, 0x08, 0x84, 0x01, 0xA0, 0xA5, 0x03, 0x88, 0x01, 0x01];
+    /// let tlv = Tlv::from_bytes(&data)?;
+    /// let template = tlv.find_by(|x| x.tag().is_constructed() && x.tag() != tlv.tag());
+    /// assert_eq!(Some(&data[5..]), template.map(|x| x.to_vec()).as_deref());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_by<P>(&self, mut predicate: P) -> Option<&Self>
+    where
+        P: FnMut(&Self) -> bool,
+    {
+        self.find_by_ref(&mut predicate)
+    }
+
+    fn find_by_ref<P>(&self, predicate: &mut P) -> Option<&Self>
+    where
+        P: FnMut(&Self) -> bool,
+    {
+        if predicate(self) {
+            return Some(self);
+        }
         match &self.value {
-            Value::Primitive(_) => {
-                if self.tag == *tag {
-                    ret.push(self);
+            Value::Primitive(_) => None,
+            Value::Constructed(e) => e.iter().find_map(|x| x.find_by_ref(predicate)),
+        }
+    }
+
+    /// Finds all data objects of self and its descendants, in document order,
+    /// for which `predicate` returns `true`.
+    pub fn find_all_by<P>(&self, mut predicate: P) -> Vec<&Self>
+    where
+        P: FnMut(&Self) -> bool,
+    {
+        let mut ret = Vec::new();
+        let _ = self.walk(&mut Vec::new(), &mut |x, _| {
+            if predicate(x) {
+                ret.push(x);
+            }
+            ControlFlow::Continue(())
+        });
+        ret
+    }
+
+    /// Finds all data objects of self and its descendants, in document order,
+    /// for which `predicate` returns `true`, along with their parents.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::convert::TryFrom;
+    /// use iso7816_tlv::ber::{Tag, Tlv};
+    /// # use iso7816_tlv::TlvError;
+    ///
+    /// # fn main() -> Result<(), TlvError> {
+    /// let data = [0x6F, 0x08, 0x88, 0x01, 0x01, 0xA5, 0x03, 0x88, 0x01, 0x02];
+    /// let tlv = Tlv::from_bytes(&data)?;
+    /// let tag = Tag::try_from(0x88)?;
+    /// let found = tlv.search_by(|x| x.tag() == &tag);
+    /// assert_eq!(2, found.len());
+    /// assert_eq!(1, found[0].parents().len());
+    /// assert_eq!("6F[0]/A5[0]/88[0]", found[1].path().to_string());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_by<P>(&self, mut predicate: P) -> Vec<Match<'_>>
+    where
+        P: FnMut(&Self) -> bool,
+    {
+        let mut ret = Vec::new();
+        let _ = self.walk(&mut Vec::new(), &mut |x, parents| {
+            if predicate(x) {
+                ret.push(Match::new(x, parents.to_vec()));
+            }
+            ControlFlow::Continue(())
+        });
+        ret
+    }
+
+    // visits self and its descendants in document order,
+    // `parents` holding the ancestors of the visited data object
+    fn walk<'a, F>(&'a self, parents: &mut Vec<&'a Self>, f: &mut F) -> ControlFlow<()>
+    where
+        F: FnMut(&'a Self, &[&'a Self]) -> ControlFlow<()>,
+    {
+        f(self, parents)?;
+        if let Value::Constructed(children) = &self.value {
+            parents.push(self);
+            for x in children {
+                x.walk(parents, f)?;
+            }
+            parents.pop();
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Get a mutable reference to the value of a primitive data object.
+    /// Returns `None` for constructed data objects, see [`children_mut()`](Self::children_mut()).
+    #[must_use]
+    pub fn value_mut(&mut self) -> Option<&mut Vec<u8>> {
+        match &mut self.value {
+            Value::Primitive(v) => Some(v),
+            Value::Constructed(_) => None,
+        }
+    }
+
+    /// Get a mutable reference to the children of a constructed data object.
+    /// Returns `None` for primitive data objects, see [`value_mut()`](Self::value_mut()).
+    ///
+    /// As children may be moved around through the returned reference,
+    /// the padding bytes preserved between them are removed (see [`Padding::Preserve`]).
+    #[must_use]
+    pub fn children_mut(&mut self) -> Option<&mut Vec<Self>> {
+        if let Some(encoding) = &mut self.encoding {
+            encoding.padding.clear();
+        }
+        self.children_and_padding_mut()
+            .map(|(children, _)| children)
+    }
+
+    // children along with the preserved padding, whose indices callers keep in sync
+    #[allow(clippy::type_complexity)]
+    fn children_and_padding_mut(&mut self) -> Option<(&mut Vec<Self>, &mut [(usize, Vec<u8>)])> {
+        let padding = match &mut self.encoding {
+            Some(e) => &mut e.padding[..],
+            None => &mut [],
+        };
+        match &mut self.value {
+            Value::Constructed(c) => Some((c, padding)),
+            Value::Primitive(_) => None,
+        }
+    }
+
+    /// Finds the first data object with given tag in self, in document order,
+    /// and returns a mutable reference to it. See [`find()`](Self::find()).
+    #[must_use]
+    pub fn find_mut(&mut self, tag: &Tag) -> Option<&mut Self> {
+        if self.tag == *tag {
+            return Some(self);
+        }
+        match &mut self.value {
+            Value::Primitive(_) => None,
+            Value::Constructed(c) => c.iter_mut().find_map(|x| x.find_mut(tag)),
+        }
+    }
+
+    /// Inserts a child at position `index` of a constructed data object.
+    /// # Errors
+    /// Fails with [`ErrorKind::Inconsistant`] if self is primitive,
+    /// or with [`ErrorKind::InvalidInput`] if `index` is greater than the number of children.
+    pub fn insert_child(&mut self, index: usize, tlv: Self) -> Result<()> {
+        let (children, padding) = self
+            .children_and_padding_mut()
+            .ok_or(ErrorKind::Inconsistant)?;
+        if index > children.len() {
+            return Err(ErrorKind::InvalidInput.into());
+        }
+        children.insert(index, tlv);
+        // padding preceding the child previously at `index` keeps preceding it
+        for (i, _) in padding.iter_mut().filter(|(i, _)| *i >= index) {
+            *i += 1;
+        }
+        Ok(())
+    }
+
+    /// Removes the first descendant of self with given tag, in document order.
+    /// Returns the removed data object, if any.
+    pub fn remove(&mut self, tag: &Tag) -> Option<Self> {
+        let (children, padding) = self.children_and_padding_mut()?;
+        for i in 0..children.len() {
+            if children[i].tag == *tag {
+                // padding preceding the removed child now precedes the next one
+                for (j, _) in padding.iter_mut().filter(|(j, _)| *j > i) {
+                    *j -= 1;
                 }
+                return Some(children.remove(i));
             }
-            Value::Constructed(e) => {
-                for x in e {
-                    let v = x.find(tag);
-                    ret.extend(v);
+            if let Some(x) = children[i].remove(tag) {
+                return Some(x);
+            }
+        }
+        None
+    }
+
+    /// Replaces the first descendant of self with given tag, in document order, by `tlv`.
+    /// Returns the replaced data object, or gives `tlv` back if no descendant has this tag.
+    /// # Errors
+    /// The returned error holds `tlv` when no descendant has given tag.
+    pub fn replace(&mut self, tag: &Tag, tlv: Self) -> core::result::Result<Self, Self> {
+        match self.find_child_mut(tag) {
+            Some(x) => Ok(core::mem::replace(x, tlv)),
+            None => Err(tlv),
+        }
+    }
+
+    // finds the first descendant, self excluded
+    fn find_child_mut(&mut self, tag: &Tag) -> Option<&mut Self> {
+        self.children_and_padding_mut()?
+            .0
+            .iter_mut()
+            .find_map(|x| x.find_mut(tag))
+    }
+
+    /// Retains only the descendants of self for which `predicate` returns `true`.
+    /// Children of a removed data object are removed along with it.
+    pub fn retain<P>(&mut self, mut predicate: P)
+    where
+        P: FnMut(&Self) -> bool,
+    {
+        self.retain_ref(&mut predicate);
+    }
+
+    fn retain_ref<P>(&mut self, predicate: &mut P)
+    where
+        P: FnMut(&Self) -> bool,
+    {
+        if let Some((children, padding)) = self.children_and_padding_mut() {
+            let kept: Vec<bool> = children.iter().map(&mut *predicate).collect();
+            // padding now precedes the next kept child
+            for (i, _) in padding.iter_mut() {
+                *i = kept[..(*i).min(kept.len())].iter().filter(|k| **k).count();
+            }
+            let mut kept = kept.into_iter();
+            children.retain(|_| kept.next().unwrap_or(false));
+            for c in children {
+                c.retain_ref(predicate);
+            }
+        }
+    }
+
+    /// Finds the first data object matching `path`, starting from self.
+    /// Contrary to [`find()`](Self::find()), each level of the tree is explicitly matched,
+    /// see [`Path`].
+    #[must_use]
+    pub fn select(&self, path: &Path) -> Option<&Self> {
+        path.select(core::slice::from_ref(self))
+    }
+
+    /// Finds all data objects matching `path`, starting from self.
+    #[must_use]
+    pub fn select_all(&self, path: &Path) -> Vec<&Self> {
+        path.select_all(core::slice::from_ref(self))
+    }
+}
+
+impl PartialEq for Tlv {
+    fn eq(&self, other: &Self) -> bool {
+        self.tag == other.tag && self.value == other.value
+    }
+}
+
+impl From<&TlvRef<'_>> for Tlv {
+    fn from(t: &TlvRef<'_>) -> Self {
+        let mut encoding = Encoding::default();
+        let value = if t.tag().is_constructed() {
+            let mut children = Vec::new();
+            let mut iter = t.children();
+            loop {
+                let p = iter.take_padding();
+                if !p.is_empty() && t.padding() == Padding::Preserve {
+                    encoding.padding.push((children.len(), p.to_vec()));
+                }
+                match iter.next() {
+                    Some(c) => children.push(Self::from(&c)),
+                    None => break,
                 }
             }
+            Value::Constructed(children)
+        } else {
+            Value::Primitive(t.value().to_vec())
+        };
+        let mut buf = [0; 9];
+        let long_len = t.length_form() == LengthForm::Definite
+            && t.length_bytes() != encode_len(t.value().len(), &mut buf);
+        if long_len && t.original_lengths() {
+            encoding.raw_len = Some(t.length_bytes().to_vec());
         }
-        ret
+        let encoding = if encoding.is_empty() {
+            None
+        } else {
+            Some(Box::new(encoding))
+        };
+        Self {
+            tag: t.tag().clone(),
+            value,
+            length_form: t.length_form(),
+            long_len,
+            encoding,
+        }
+    }
+}
+
+/// Serialized as a struct with `tag` and `value` fields,
+/// see [`Value`] for the representation of values.
+/// The length form, padding and parsed length fields are not serialized.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Tlv {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> core::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut tlv = s.serialize_struct("Tlv", 2)?;
+        tlv.serialize_field("tag", &self.tag)?;
+        tlv.serialize_field("value", &self.value)?;
+        tlv.end()
+    }
+}
+
+/// Fails if the value is not consistent with the tag, as [`Tlv::new()`].
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Tlv {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> core::result::Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Tlv")]
+        struct Repr {
+            tag: Tag,
+            value: Value,
+        }
+
+        let Repr { tag, value } = Repr::deserialize(d)?;
+        Self::new(tag, value).map_err(serde::de::Error::custom)
     }
 }
 
 impl fmt::Display for Tlv {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}, ", self.tag)?;
-        write!(f, "len={}, ", self.value.len_as_bytes())?;
+        write!(f, "len={}, ", self.value_len(false, None))?;
         write!(f, "value:")?;
 
         match &self.value {
@@ -263,7 +930,7 @@ impl fmt::Display for Tlv {
                     )?;
                 }
             }
-        };
+        }
         Ok(())
     }
 }
@@ -271,6 +938,7 @@ impl fmt::Display for Tlv {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::string::ToString;
     use core::convert::TryFrom;
 
     #[test]
@@ -286,8 +954,7 @@ mod tests {
             assert_eq!(Tag::try_from(1_u32)?, *tlv.tag());
             assert_eq!(Value::Primitive(data), *tlv.value());
 
-            let mut r = Reader::new(Input::from(&expected));
-            let read = Tlv::read(&mut r)?;
+            let read = Tlv::from_bytes(&expected)?;
             assert_eq!(tlv, read);
         }
         {
@@ -299,8 +966,7 @@ mod tests {
             assert_eq!(Tag::try_from(1_u32)?, *tlv.tag());
             assert_eq!(Value::Primitive(data), *tlv.value());
 
-            let mut r = Reader::new(Input::from(&expected));
-            let read = Tlv::read(&mut r)?;
+            let read = Tlv::from_bytes(&expected)?;
             assert_eq!(tlv, read);
         }
         {
@@ -312,8 +978,7 @@ mod tests {
             assert_eq!(Tag::try_from(1_u32)?, *tlv.tag());
             assert_eq!(Value::Primitive(data), *tlv.value());
 
-            let mut r = Reader::new(Input::from(&expected));
-            let read = Tlv::read(&mut r)?;
+            let read = Tlv::from_bytes(&expected)?;
             assert_eq!(tlv, read);
         }
 
@@ -336,18 +1001,16 @@ mod tests {
         assert_eq!(Tag::try_from("7f22")?, *tlv.tag());
         assert_eq!(construct, *tlv.value());
 
-        let mut r = Reader::new(Input::from(&expected));
-        let read = Tlv::read(&mut r)?;
+        let read = Tlv::from_bytes(&expected)?;
         assert_eq!(tlv, read);
 
         construct.push(base.clone())?;
-        expected[2] += base.len() as u8;
+        expected[2] += base.encoded_len() as u8;
         expected.append(&mut base.to_vec());
         let tlv = Tlv::new(Tag::try_from("7f22")?, construct)?;
         assert_eq!(expected, tlv.to_vec());
 
-        let mut r = Reader::new(Input::from(&expected));
-        let read = Tlv::read(&mut r)?;
+        let read = Tlv::from_bytes(&expected)?;
         assert_eq!(tlv, read);
 
         Ok(())
@@ -369,10 +1032,502 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn padding() -> Result<()> {
+        let input = hex!(
+            "00 FF"
+            "70 0C 00 5A 02 1234 FFFF 5F20 01 41 00"
+            "FF FF"
+        );
+        let tlvs = Tlv::parse_all(&input);
+        assert_eq!(1, tlvs.len());
+        let skipped = &tlvs[0];
+        assert_eq!(
+            hex!("70 08 5A 02 1234 5F20 01 41").to_vec(),
+            skipped.to_vec()
+        );
+        assert!(skipped.find(&Tag::try_from(0x5F20)?).is_some());
+
+        // skipped by default, at top level and inside constructed values
+        let padded = hex!("00 70 04 80 01 AA 00 FF");
+        let tlv = Tlv::from_bytes(&padded)?;
+        assert_eq!(hex!("70 03 80 01 AA").to_vec(), tlv.to_vec());
+        assert_eq!(&padded[1..7], TlvRef::from_bytes(&padded)?.as_bytes());
+        let (tlv, rest) = Tlv::parse(&padded);
+        assert_eq!(hex!("70 03 80 01 AA").to_vec(), tlv?.to_vec());
+        assert!(rest.is_empty());
+
+        let reject = ParseOptions::new().padding(Padding::Reject);
+        assert!(Tlv::parse_all_with(&input, &reject).is_empty());
+        let (tlv, rest) = Tlv::parse_with(&input[2..], &reject);
+        assert_eq!(Err(ErrorKind::InvalidInput), tlv.map_err(|e| e.kind()));
+        assert_eq!(&input[2..], rest);
+
+        let options = ParseOptions::new().padding(Padding::Preserve);
+        let mut preserved = Tlv::parse_all_with(&input, &options);
+        assert_eq!(1, preserved.len());
+        assert_eq!(input.to_vec(), preserved[0].to_vec());
+        assert_eq!(18, preserved[0].encoded_len());
+        let (tlv, rest) = Tlv::parse_with(&input, &options);
+        assert_eq!(input.to_vec(), tlv?.to_vec());
+        assert!(rest.is_empty());
+        preserved[0].remove_padding();
+        assert_eq!(skipped, &preserved[0]);
+
+        // records read from a card with their trailing padding
+        let records = hex!(
+            "70 03 5A 01 01 FF FF"
+            "70 03 5A 01 02 00 00"
+            "70 03 5A 01 03 FF"
+        );
+        let tlvs = Tlv::parse_all_with(&records, &options);
+        assert_eq!(3, tlvs.len());
+        assert_eq!(&records[..7], &tlvs[0].to_vec()[..]);
+        let encoded: Vec<u8> = tlvs.iter().flat_map(Tlv::to_vec).collect();
+        assert_eq!(records.to_vec(), encoded);
+        let mut buf = [0; 20];
+        let mut sink = &mut buf[..];
+        for t in &tlvs {
+            t.encode_to(&mut sink)?;
+        }
+        assert_eq!(records, buf);
+        // top-level padding is not written along with children
+        let parent = Tlv::new(Tag::try_from(0x70)?, Value::Constructed(tlvs))?;
+        assert_eq!(
+            hex!("70 0F 70 03 5A 01 01 70 03 5A 01 02 70 03 5A 01 03").to_vec(),
+            parent.to_vec()
+        );
+
+        // in indefinite length values, '00' starts the end-of-contents
+        let input = hex!("70 80 FF 5A 01 01 FF 0000");
+        let (tlv, rest) = Tlv::parse_with(&input, &options);
+        assert_eq!(input.to_vec(), tlv?.to_vec());
+        assert!(rest.is_empty());
+        let input = hex!("70 80 00 5A 01 01 0000");
+        assert!(Tlv::parse_with(&input, &options).0.is_err());
+
+        // borrowed data objects skip padding as well
+        let input = hex!("70 09 5A 01 01 00 FF 5A 01 02 FF");
+        let tlv = TlvRef::parse_with(&input, &options).0?;
+        assert_eq!(&input[..], tlv.as_bytes());
+        assert_eq!(2, tlv.children().count());
+        Ok(())
+    }
+
+    #[test]
+    fn wrap_padded() -> Result<()> {
+        let input = hex!("00 80 01 AA 00 81 00 FF");
+        let options = ParseOptions::new().padding(Padding::Preserve);
+        let children = Tlv::parse_all_with(&input, &options);
+        assert_eq!(
+            input.to_vec(),
+            children.iter().flat_map(Tlv::to_vec).collect::<Vec<_>>()
+        );
+
+        // padding around top-level data objects is dropped once nested
+        let value = Value::Constructed(children);
+        assert_eq!(5, value.len_as_bytes());
+        let tlv = Tlv::new(Tag::try_from(0xA0)?, value)?;
+        assert_eq!(hex!("A0 05 80 01 AA 81 00").to_vec(), tlv.to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn edit_padded() -> Result<()> {
+        let input = hex!("70 0D FF 5A 01 01 00 5A 01 02 FF 5A 01 03 00");
+        let options = ParseOptions::new().padding(Padding::Preserve);
+        let parse = || Tlv::parse_with(&input, &options).0;
+        let tag = Tag::try_from(0x5A)?;
+        let added = Tlv::new(Tag::try_from(0x5F20)?, Value::Primitive(vec![0x41]))?;
+
+        let mut tlv = parse()?;
+        tlv.insert_child(1, added.clone())?;
+        assert_eq!(
+            hex!("70 11 FF 5A 01 01 5F20 01 41 00 5A 01 02 FF 5A 01 03 00").to_vec(),
+            tlv.to_vec()
+        );
+        tlv.insert_child(4, added.clone())?;
+        assert_eq!(
+            hex!("70 15 FF 5A 01 01 5F20 01 41 00 5A 01 02 FF 5A 01 03 5F20 01 41 00").to_vec(),
+            tlv.to_vec()
+        );
+
+        let mut tlv = parse()?;
+        assert!(tlv.remove(&tag).is_some());
+        assert_eq!(
+            hex!("70 0A FF 00 5A 01 02 FF 5A 01 03 00").to_vec(),
+            tlv.to_vec()
+        );
+
+        let mut tlv = parse()?;
+        assert!(tlv.replace(&tag, added).is_ok());
+        assert_eq!(
+            hex!("70 0E FF 5F20 01 41 00 5A 01 02 FF 5A 01 03 00").to_vec(),
+            tlv.to_vec()
+        );
+
+        let mut tlv = parse()?;
+        tlv.retain(|x| x.value() != &Value::Primitive(vec![0x02]));
+        assert_eq!(
+            hex!("70 0A FF 5A 01 01 00 FF 5A 01 03 00").to_vec(),
+            tlv.to_vec()
+        );
+
+        let mut tlv = parse()?;
+        if let Some(children) = tlv.children_mut() {
+            children.swap(0, 2);
+        }
+        assert_eq!(
+            hex!("70 09 5A 01 03 5A 01 02 5A 01 01").to_vec(),
+            tlv.to_vec()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn der() -> Result<()> {
+        let der = hex!("70 07 5A 01 00 5F20 01 41");
+        let not_der = [
+            // length fields
+            &hex!("70 08 5A 81 01 00 5F20 01 41")[..],
+            &hex!("70 82 0007 5A 01 00 5F20 01 41"),
+            &hex!("70 80 5A 01 00 5F20 01 41 0000"),
+            // tags
+            &hex!("70 08 1F1A 01 00 5F20 01 41"),
+            &hex!("70 08 5A 01 00 5F8020 01 41"),
+        ];
+        assert!(Tlv::parse_der(&der).0?.is_der());
+        assert!(TlvRef::parse_der(&der).0?.is_der());
+        for input in not_der {
+            assert!(!Tlv::from_bytes(input)?.is_der(), "{:02X?}", input);
+            assert!(!TlvRef::from_bytes(input)?.is_der(), "{:02X?}", input);
+            assert!(Tlv::parse_der(input).0.is_err(), "{:02X?}", input);
+            assert!(TlvRef::parse_der(input).0.is_err(), "{:02X?}", input);
+        }
+
+        // padding
+        let input = hex!("70 08 5A 01 00 FF 5F20 01 41");
+        let options = ParseOptions::new().padding(Padding::Preserve);
+        assert!(!Tlv::parse_with(&input, &options).0?.is_der());
+        assert!(!TlvRef::parse_with(&input, &options).0?.is_der());
+        assert!(Tlv::parse_der(&input).0.is_err());
+
+        // the encoding is part of the parsed data object
+        let tlv = Tlv::new(Tag::try_from(0x5A)?, Value::Primitive(vec![0]))?;
+        assert!(tlv.is_der());
+        let parsed = Tlv::from_bytes(&hex!("5A 81 01 00"))?;
+        assert!(!parsed.is_der());
+        assert_eq!(tlv, parsed);
+        assert_eq!(tlv.to_vec(), parsed.to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn original_encoding() -> Result<()> {
+        let input = hex!("70 81 11 5A 81 08 1122334455667788 5F24 03 251231");
+        let minimal = hex!("70 10 5A 08 1122334455667788 5F24 03 251231");
+        // length fields are only kept on request
+        let tlv = Tlv::from_bytes(&input)?;
+        assert_eq!(minimal.to_vec(), tlv.to_vec_original());
+        let options = ParseOptions::new().original_lengths(true);
+        let mut tlv = Tlv::parse_with(&input, &options).0?;
+        assert_eq!(input.to_vec(), tlv.to_vec_original());
+        assert_eq!(minimal.to_vec(), tlv.to_vec());
+        assert_eq!(input.len() - 2, tlv.encoded_len());
+        let pan = tlv
+            .find(&Tag::try_from(0x5A)?)
+            .ok_or(ErrorKind::InvalidInput)?;
+        assert_eq!(&input[3..14], &pan.to_vec_original()[..]);
+
+        // stale length fields are re-encoded
+        let expiry = tlv
+            .find_mut(&Tag::try_from(0x5F24)?)
+            .ok_or(ErrorKind::InvalidInput)?;
+        expiry
+            .value_mut()
+            .ok_or(ErrorKind::InvalidInput)?
+            .push(0x00);
+        assert_eq!(
+            hex!("70 12 5A 81 08 1122334455667788 5F24 04 25123100").to_vec(),
+            tlv.to_vec_original()
+        );
+
+        // along with tags, length forms and preserved padding
+        let input = hex!("7F22 80 1F01 82 0001 01 FF A1 80 0101AA 0000 0000");
+        let options = options.padding(Padding::Preserve);
+        let tlv = Tlv::parse_with(&input, &options).0?;
+        assert_eq!(input.to_vec(), tlv.to_vec_original());
+        assert_eq!(
+            hex!("7F22 80 1F01 01 01 FF A1 80 0101AA 0000 0000").to_vec(),
+            tlv.to_vec()
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() -> Result<()> {
+        use serde_test::{assert_de_tokens, assert_de_tokens_error, assert_tokens, Token};
+
+        let tlv = Tlv::from_bytes(&hex!("6F 0A 84 02 A000 A5 04 9F4D 01 0B"))?;
+        assert_tokens(
+            &tlv,
+            &[
+                Token::Struct {
+                    name: "Tlv",
+                    len: 2,
+                },
+                Token::Str("tag"),
+                Token::Str("6F"),
+                Token::Str("value"),
+                Token::Seq { len: Some(2) },
+                Token::Struct {
+                    name: "Tlv",
+                    len: 2,
+                },
+                Token::Str("tag"),
+                Token::Str("84"),
+                Token::Str("value"),
+                Token::Str("A000"),
+                Token::StructEnd,
+                Token::Struct {
+                    name: "Tlv",
+                    len: 2,
+                },
+                Token::Str("tag"),
+                Token::Str("A5"),
+                Token::Str("value"),
+                Token::Seq { len: Some(1) },
+                Token::Struct {
+                    name: "Tlv",
+                    len: 2,
+                },
+                Token::Str("tag"),
+                Token::Str("9F4D"),
+                Token::Str("value"),
+                Token::Str("0B"),
+                Token::StructEnd,
+                Token::SeqEnd,
+                Token::StructEnd,
+                Token::SeqEnd,
+                Token::StructEnd,
+            ],
+        );
+
+        // lower case hex is accepted
+        let tlv = Tlv::new(Tag::try_from(0x9F4D)?, Value::Primitive(vec![0x0B, 0x0A]))?;
+        let tokens = |tag, value| {
+            [
+                Token::Struct {
+                    name: "Tlv",
+                    len: 2,
+                },
+                Token::Str("tag"),
+                Token::Str(tag),
+                Token::Str("value"),
+                Token::Str(value),
+                Token::StructEnd,
+            ]
+        };
+        assert_de_tokens(&tlv, &tokens("9f4d", "0b0a"));
+        let invalid = [
+            (
+                "9F4D",
+                "A",
+                "invalid value: string \"A\", expected a hex string",
+            ),
+            (
+                "9F4D",
+                "0G",
+                "invalid value: string \"0G\", expected a hex string",
+            ),
+            ("6F", "00", "Inconsistant (tag, value) pair"),
+        ];
+        for (tag, value, error) in invalid {
+            assert_de_tokens_error::<Tlv>(&tokens(tag, value), error);
+        }
+        assert_de_tokens_error::<Tag>(&[Token::Str("XX")], "Error parsing input as int");
+
+        assert_tokens(
+            &super::super::Form::Constructed,
+            &[Token::UnitVariant {
+                name: "Form",
+                variant: "Constructed",
+            }],
+        );
+        assert_tokens(
+            &Tag::try_from(0x7F49)?.class(),
+            &[Token::UnitVariant {
+                name: "Class",
+                variant: "Application",
+            }],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn indefinite_length() -> Result<()> {
+        let input = hex!("7F22 80 010100 A1 80 0101AA 0000 0000");
+        let mut tlv = Tlv::from_bytes(&input)?;
+        assert_eq!(LengthForm::Indefinite, tlv.length_form());
+        assert_eq!(input.len(), tlv.encoded_len());
+        assert_eq!(input.to_vec(), tlv.to_vec());
+
+        let a1 = tlv.find_all(&Tag::try_from(1_u32)?);
+        assert_eq!(2, a1.len());
+
+        tlv.make_definite();
+        assert_eq!(LengthForm::Definite, tlv.length_form());
+        assert_eq!(hex!("7F22 08 010100 A1 03 0101AA").to_vec(), tlv.to_vec());
+        assert_eq!(tlv.encoded_len(), tlv.to_vec().len());
+
+        tlv.set_length_form(LengthForm::Indefinite)?;
+        assert_eq!(
+            hex!("7F22 80 010100 A1 03 0101AA 0000").to_vec(),
+            tlv.to_vec()
+        );
+
+        let mut primitive = Tlv::new(Tag::try_from(1_u32)?, Value::Primitive(vec![0]))?;
+        assert_eq!(
+            Err(ErrorKind::Inconsistant),
+            primitive
+                .set_length_form(LengthForm::Indefinite)
+                .map_err(|e| e.kind())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn encode_into() -> Result<()> {
+        let base = Tlv::new(Tag::try_from(0x80_u32)?, Value::Primitive(vec![0xAA; 127]))?;
+        let mut construct = Tlv::new(
+            Tag::try_from("7f22")?,
+            Value::Constructed(vec![base.clone(), base]),
+        )?;
+        let expected = construct.to_vec();
+        // 127 bytes long values use a one byte length field
+        assert_eq!(&hex!("7F22 82 0102 80 7F AA"), &expected[..8]);
+        assert_eq!(expected.len(), construct.encoded_len());
+
+        let mut buf = [0_u8; 300];
+        let len = construct.encode_into(&mut buf)?;
+        assert_eq!(expected.len(), len);
+        assert_eq!(&expected[..], &buf[..len]);
+        assert_eq!(
+            Err(ErrorKind::BufferTooSmall),
+            construct
+                .encode_into(&mut buf[..len - 1])
+                .map_err(|e| e.kind())
+        );
+
+        construct.set_length_form(LengthForm::Indefinite)?;
+        let len = construct.encode_into(&mut buf)?;
+        assert_eq!(construct.to_vec(), &buf[..len]);
+
+        let mut sink = Vec::new();
+        construct.encode_to(&mut sink)?;
+        assert_eq!(construct.to_vec(), sink);
+
+        // value lengths missing for the children
+        let mut lens = core::iter::once(construct.value_len(false, None));
+        assert_eq!(
+            Err(ErrorKind::Inconsistant),
+            construct
+                .encode_with(false, &mut lens, &mut |b| sink.write_all(b))
+                .map_err(|e| e.kind())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn encode_deep() -> Result<()> {
+        // each length is computed once, whatever the depth
+        let leaf = Tlv::new(Tag::try_from(0x80_u32)?, Value::Primitive(vec![0xAA; 100]))?;
+        let mut tlv = leaf.clone();
+        for _ in 0..200 {
+            tlv = Tlv::new(
+                Tag::try_from(0xA1_u32)?,
+                Value::Constructed(vec![tlv, leaf.clone()]),
+            )?;
+        }
+        let expected = tlv.to_vec();
+        assert_eq!(expected.len(), tlv.encoded_len());
+        assert_eq!(21_301, expected.len());
+        assert_eq!(&hex!("A1 82 5331 A1 82 52C7"), &expected[..8]);
+
+        let mut buf = vec![0; expected.len()];
+        assert_eq!(expected.len(), tlv.encode_into(&mut buf)?);
+        assert_eq!(expected, buf);
+
+        let mut sink = Vec::new();
+        tlv.encode_to(&mut sink)?;
+        assert_eq!(expected, sink);
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn encode_to_io() -> Result<()> {
+        let tlv = Tlv::new(Tag::try_from(0x80_u32)?, Value::Primitive(vec![0xAA; 4]))?;
+        let mut sink = IoSink::new(std::io::Cursor::new(Vec::new()));
+        tlv.encode_to(&mut sink)?;
+        assert_eq!(tlv.to_vec(), sink.into_inner().into_inner());
+
+        let mut buf = [0_u8; 4];
+        assert_eq!(
+            Err(ErrorKind::WriteError),
+            tlv.encode_to(&mut &mut buf[..]).map_err(|e| e.kind())
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn read_write_io() -> Result<()> {
+        let input = hex!("6F 07 84 05 A000000003 7F22 80 010100 0000 9F7F 02 0102");
+        let mut reader = &input[..];
+        let tlv = Tlv::read_from(&mut reader)?;
+        assert_eq!(&input[..9], &tlv.to_vec()[..]);
+        assert_eq!(
+            LengthForm::Indefinite,
+            Tlv::read_from(&mut reader)?.length_form()
+        );
+        assert_eq!(&input[17..], reader);
+        let last = Tlv::read_from(&mut reader)?;
+        assert_eq!(
+            Err(ErrorKind::TruncatedInput),
+            Tlv::read_from(&mut reader).map_err(|e| e.kind())
+        );
+
+        let mut out = Vec::new();
+        tlv.write_to(&mut out)?;
+        last.write_to(&mut out)?;
+        assert_eq!(&input[..9], &out[..9]);
+        assert_eq!(&input[17..], &out[9..]);
+
+        // long value claimed, data missing
+        let mut reader = &hex!("04 84 7FFFFFFF 00")[..];
+        assert_eq!(
+            Err(ErrorKind::TruncatedInput),
+            Tlv::read_from(&mut reader).map_err(|e| e.kind())
+        );
+        // rejected without reading the value
+        let mut reader = &hex!("04 84 7FFFFFFF 00")[..];
+        let options = ParseOptions::new().max_len(1024);
+        assert_eq!(
+            Err(ErrorKind::LimitExceeded),
+            Tlv::read_from_with(&mut reader, &options).map_err(|e| e.kind())
+        );
+        assert_eq!(1, reader.len());
+        Ok(())
+    }
+
     #[cfg(feature = "std")]
     #[test]
     #[allow(clippy::redundant_clone)] // keep redundant_clone to have fewer modification if test is expanded
     fn display() -> Result<()> {
+        use std::println;
+
         let base = Tlv::new(Tag::try_from(0x80_u32)?, Value::Primitive(vec![0]))?;
         let construct = Value::Constructed(vec![base.clone(), base.clone()]);
         let tlv = Tlv::new(Tag::try_from("7f22")?, construct.clone())?;
@@ -382,7 +1537,7 @@ mod tests {
         construct2.push(base)?;
         let t = Tag::try_from("3F32")?;
         let tlv = Tlv::new(t, construct2)?;
-        println!("{}", tlv);
+        println!("{tlv}");
         Ok(())
     }
 
@@ -423,4 +1578,109 @@ mod tests {
         assert_eq!(2, tlv.find_all(&Tag::try_from(0x80_u32)?).len());
         Ok(())
     }
+
+    #[test]
+    fn search() -> Result<()> {
+        let input = hex!(
+            "6F 17"
+            "84 02 A000"
+            "A5 09 88 01 01 BF0C 03 88 01 02"
+            "A5 06 88 01 03 87 01 01"
+        );
+        let tlv = Tlv::from_bytes(&input)?;
+        let tag = Tag::try_from(0x88)?;
+
+        // all occurences, including nested ones, in document order
+        let found = tlv.find_all(&tag);
+        let values: Vec<&Value> = found.iter().map(|x| x.value()).collect();
+        assert_eq!(
+            vec![
+                &Value::Primitive(vec![1]),
+                &Value::Primitive(vec![2]),
+                &Value::Primitive(vec![3])
+            ],
+            values
+        );
+
+        // constructed data objects
+        assert_eq!(2, tlv.find_all(&Tag::try_from(0xA5)?).len());
+        let bf0c = tlv
+            .find(&Tag::try_from(0xBF0C)?)
+            .ok_or(ErrorKind::InvalidInput)?;
+        assert_eq!(&input[11..17], &bf0c.to_vec()[..]);
+        assert_eq!(Some(&tlv), tlv.find(&Tag::try_from(0x6F)?));
+
+        let found = tlv.find_by(|x| x.value() == &Value::Primitive(vec![1]));
+        assert_eq!(Some(&Tag::try_from(0x88)?), found.map(Tlv::tag));
+        let one_byte =
+            tlv.find_all_by(|x| matches!(x.value(), Value::Primitive(v) if v.len() == 1));
+        assert_eq!(4, one_byte.len());
+
+        let found = tlv.search_by(|x| x.tag() == &tag);
+        assert_eq!(3, found.len());
+        assert_eq!(3, found[1].parents().len());
+        assert_eq!(&tlv, found[1].parents()[0]);
+        for m in &found {
+            let path = m.path();
+            assert_eq!(Some(m.tlv()), tlv.select(&path));
+            assert_eq!(path, path.to_string().parse()?);
+        }
+        assert_eq!("6F[0]/A5[1]/88[0]", found[2].path().to_string());
+        assert_eq!("6F[0]", tlv.search_by(|_| true)[0].path().to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn edit() -> Result<()> {
+        let input = hex!(
+            "6F 12"
+            "84 02 A000"
+            "A5 0C 88 01 01 BF0C 06 9F4D 01 0B 88 00"
+        );
+        let mut tlv = Tlv::from_bytes(&input)?;
+        let t88 = Tag::try_from(0x88)?;
+        let t9f4d = Tag::try_from(0x9F4D)?;
+
+        // patch a nested primitive value
+        let sfi = tlv.find_mut(&t9f4d).ok_or(ErrorKind::InvalidInput)?;
+        sfi.value_mut().ok_or(ErrorKind::InvalidInput)?[0] = 0x0C;
+        assert!(sfi.children_mut().is_none());
+        assert_eq!(
+            Some(&Value::Primitive(vec![0x0C])),
+            tlv.find(&t9f4d).map(Tlv::value)
+        );
+        assert!(tlv.value_mut().is_none());
+        assert_eq!(2, tlv.children_mut().map_or(0, |c| c.len()));
+
+        // insertion keeps encoding consistent
+        let aid = Tlv::new(Tag::try_from(0x4F)?, Value::Primitive(vec![0xA0, 0x01]))?;
+        tlv.insert_child(1, aid.clone())?;
+        assert_eq!(
+            Err(ErrorKind::InvalidInput),
+            tlv.insert_child(4, aid.clone()).map_err(|e| e.kind())
+        );
+        let mut primitive = aid.clone();
+        assert_eq!(
+            Err(ErrorKind::Inconsistant),
+            primitive.insert_child(0, aid.clone()).map_err(|e| e.kind())
+        );
+        let encoded = tlv.to_vec();
+        assert_eq!(input.len() + 4, encoded.len());
+        assert_eq!(tlv, Tlv::from_bytes(&encoded)?);
+
+        // remove and replace in document order, self excluded
+        let removed = tlv.remove(&t88).ok_or(ErrorKind::InvalidInput)?;
+        assert_eq!(&Value::Primitive(vec![1]), removed.value());
+        assert_eq!(Ok(aid.clone()), tlv.replace(aid.tag(), removed.clone()));
+        assert_eq!(Err(aid.clone()), tlv.replace(aid.tag(), aid.clone()));
+        assert_eq!(2, tlv.find_all(&t88).len());
+        assert!(tlv.remove(&Tag::try_from(0x6F)?).is_none());
+
+        tlv.retain(|x| x.tag() != &t88);
+        assert!(tlv.find(&t88).is_none());
+        assert!(tlv.find(&t9f4d).is_some());
+        tlv.retain(|x| !x.tag().is_constructed());
+        assert_eq!(hex!("6F 04 84 02 A000").to_vec(), tlv.to_vec());
+        Ok(())
+    }
 }
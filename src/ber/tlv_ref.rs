@@ -0,0 +1,1037 @@
+//! Borrowed (zero-copy) view over BER-TLV data as defined in [ISO7816-4]
+//!
+
+use untrusted::{Input, Reader};
+
+use super::length::{encode_len, read_len};
+#[cfg(feature = "alloc")]
+use super::Tlv;
+use super::{LengthForm, Padding, ParseOptions, Tag};
+use crate::{ErrorKind, Result, Sink, TlvError};
+
+/// Borrowed BER-TLV data object.
+///
+/// Contrary to [`Tlv`], parsing a `TlvRef` does not copy nor allocate anything:
+/// only the tag is decoded, value and raw encoding are slices of the parsed input.
+///
+/// The whole tree is checked when parsing, so walking through
+/// [`children()`](Self::children()) afterwards cannot fail.
+///
+/// # Example
+/// ```rust
+/// use std::convert::TryFrom;
+/// use iso7816_tlv::ber::{Tag, TlvRef};
+/// # use iso7816_tlv::{ErrorKind, TlvError};
+///
+/// # fn main() -> Result<(), TlvError> {
+/// let data = [0x6F, 0x07, 0x84, 0x05, 0xA0, 0x00, 0x00, 0x00, 0x03];
+/// let tlv = TlvRef::from_bytes(&data)?;
+/// assert_eq!(&Tag::try_from(0x6F)?, tlv.tag());
+/// assert_eq!(&data[..], tlv.as_bytes());
+///
+/// let aid = tlv.find(&Tag::try_from(0x84)?).ok_or(ErrorKind::InvalidInput)?;
+/// assert_eq!(&data[4..], aid.value());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TlvRef<'a> {
+    tag: Tag,
+    value: &'a [u8],
+    raw: &'a [u8],
+    length_form: LengthForm,
+    padding: Padding,
+    original_lengths: bool,
+}
+
+impl<'a> TlvRef<'a> {
+    /// Creates a BER-TLV data object by encoding tag and value at the beginning of `buf`,
+    /// without allocating. The length field is encoded in definite form.
+    ///
+    /// For a constructed tag, `value` is the encoding of the children,
+    /// for instance the concatenation of their [`as_bytes()`](Self::as_bytes()).
+    ///
+    /// # Example
+    /// ```rust
+    /// use iso7816_tlv::ber::{Tag, TlvRef};
+    /// use iso7816_tlv::ber_tag;
+    ///
+    /// # fn main() -> Result<(), iso7816_tlv::TlvError> {
+    /// let mut aid_buf = [0; 16];
+    /// let aid = TlvRef::new(&ber_tag!(0x84), &[0xA0, 0x00, 0x00, 0x00, 0x03], &mut aid_buf)?;
+    ///
+    /// let mut buf = [0; 16];
+    /// let fci = TlvRef::new(&ber_tag!(0x6F), aid.as_bytes(), &mut buf)?;
+    /// assert_eq!(&[0x6F, 0x07, 0x84, 0x05, 0xA0, 0x00, 0x00, 0x00, 0x03], fci.as_bytes());
+    /// assert_eq!(Some(aid), fci.children().next());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Fails with [`ErrorKind::BufferTooSmall`] if `buf` cannot hold the encoded data object,
+    /// or if the value of a constructed data object is not valid BER-TLV.
+    pub fn new(tag: &Tag, value: &[u8], buf: &'a mut [u8]) -> Result<Self> {
+        let mut len_buf = [0; 9];
+        let length = encode_len(value.len(), &mut len_buf);
+        let len = tag.len_as_bytes() + length.len() + value.len();
+        let out = buf.get_mut(..len).ok_or(ErrorKind::BufferTooSmall)?;
+        let mut sink = &mut *out;
+        sink.write_all(tag.to_bytes())?;
+        sink.write_all(length)?;
+        sink.write_all(value)?;
+        Self::from_bytes(out)
+    }
+
+    /// Get BER-TLV tag.
+    #[must_use]
+    pub fn tag(&self) -> &Tag {
+        &self.tag
+    }
+
+    /// Get BER-TLV length, tag and length fields included, as found in the input.
+    /// The value length is `value().len()`.
+    #[must_use]
+    pub fn length(&self) -> usize {
+        self.raw.len()
+    }
+
+    /// Get the form of the length field used in the parsed input.
+    #[must_use]
+    pub fn length_form(&self) -> LengthForm {
+        self.length_form
+    }
+
+    /// Get BER-TLV value as a slice of the parsed input.
+    /// For constructed data objects, this is the BER-TLV encoding of the children
+    /// (without the end-of-contents bytes for the indefinite length form).
+    #[must_use]
+    pub fn value(&self) -> &'a [u8] {
+        self.value
+    }
+
+    /// Get the whole BER-TLV encoding (tag, length, value and end-of-contents if any)
+    /// as a slice of the parsed input.
+    #[must_use]
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// Get the length field as found in the parsed input.
+    #[must_use]
+    pub fn length_bytes(&self) -> &'a [u8] {
+        let tag_len = self.tag.len_as_bytes();
+        match self.length_form {
+            LengthForm::Definite => &self.raw[tag_len..self.raw.len() - self.value.len()],
+            LengthForm::Indefinite => &self.raw[tag_len..=tag_len],
+        }
+    }
+
+    /// Whether self was encoded following the Distinguished Encoding Rules (DER),
+    /// that is without any of:
+    /// - length fields in the indefinite form,
+    /// - length fields not encoded on the minimal number of bytes,
+    /// - tags not encoded on the minimal number of bytes,
+    /// - padding bytes.
+    ///
+    /// Nested data objects are checked as well.
+    ///
+    /// # Example
+    /// ```rust
+    /// use iso7816_tlv::ber::TlvRef;
+    /// # use iso7816_tlv::TlvError;
+    ///
+    /// # fn main() -> Result<(), TlvError> {
+    /// assert!(TlvRef::from_bytes(&[0x70, 0x03, 0x5A, 0x01, 0x00])?.is_der());
+    /// assert!(!TlvRef::from_bytes(&[0x70, 0x04, 0x5A, 0x81, 0x01, 0x00])?.is_der());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn is_der(&self) -> bool {
+        let mut buf = [0; 9];
+        if self.length_form != LengthForm::Definite
+            || !self.tag.is_minimal()
+            || self.length_bytes() != encode_len(self.value.len(), &mut buf)
+        {
+            return false;
+        }
+        let mut len = 0;
+        for c in self.children() {
+            if !c.is_der() {
+                return false;
+            }
+            len += c.raw.len();
+        }
+        // padding
+        !self.tag.is_constructed() || len == self.value.len()
+    }
+
+    /// Iterates over the children of a constructed data object.
+    /// The iterator is empty for primitive data objects.
+    #[must_use]
+    pub fn children(&self) -> Children<'a> {
+        let rest = if self.tag.is_constructed() {
+            self.value
+        } else {
+            &[]
+        };
+        Children {
+            rest,
+            padding: self.padding,
+            original_lengths: self.original_lengths,
+        }
+    }
+
+    // how padding was handled when parsing self
+    #[cfg(feature = "alloc")]
+    pub(crate) fn padding(&self) -> Padding {
+        self.padding
+    }
+
+    // whether parsed length fields are kept when converting self into a `Tlv`
+    #[cfg(feature = "alloc")]
+    pub(crate) fn original_lengths(&self) -> bool {
+        self.original_lengths
+    }
+
+    /// Converts self into an owned [`Tlv`].
+    #[cfg(feature = "alloc")]
+    #[must_use]
+    pub fn to_tlv(&self) -> Tlv {
+        Tlv::from(self)
+    }
+
+    /// Copies the encoding of self into the beginning of `buf`.
+    /// Returns the number of bytes written.
+    /// # Errors
+    /// Fails with [`ErrorKind::BufferTooSmall`] if `buf` cannot hold the encoded data object.
+    pub fn encode_into(&self, buf: &mut [u8]) -> Result<usize> {
+        let out = buf
+            .get_mut(..self.raw.len())
+            .ok_or(ErrorKind::BufferTooSmall)?;
+        out.copy_from_slice(self.raw);
+        Ok(self.raw.len())
+    }
+
+    /// Writes the encoding of self into a byte [`Sink`].
+    /// # Errors
+    /// Fails if the sink fails, see [`Sink::write_all()`].
+    pub fn encode_to<S: Sink + ?Sized>(&self, sink: &mut S) -> Result<()> {
+        sink.write_all(self.raw)
+    }
+
+    // reads tag, length and value at the beginning of input, without checking the value content
+    // of definite length data objects.
+    // `offset` and `depth` locate input in the parsed data, for error reporting.
+    pub(super) fn read_unchecked(
+        input: &'a [u8],
+        offset: usize,
+        depth: usize,
+        options: &ParseOptions,
+    ) -> Result<Self> {
+        if depth > options.max_depth {
+            let e = TlvError::from(ErrorKind::DepthLimitExceeded);
+            return Err(e.located(offset, None, depth));
+        }
+        let mut r = Reader::new(Input::from(input));
+        let tag = Tag::read(&mut r).map_err(|e| e.located(offset, None, depth))?;
+        let located = |e: TlvError| e.located(offset, Some(&tag), depth);
+        if (options.iso7816_tags && !tag.iso7816_compliant())
+            || (options.minimal_tags && !tag.is_minimal())
+        {
+            return Err(located(ErrorKind::InvalidInput.into()));
+        }
+        let len = read_len(&mut r, options.minimal_lengths).map_err(located)?;
+        if len.is_none() && options.definite_lengths {
+            return Err(located(ErrorKind::InvalidLength.into()));
+        }
+        let header_len = input.len() - r.read_bytes_to_end().len();
+        // only top-level data objects are limited in length
+        let max_len = if depth == 0 {
+            options.max_len
+        } else {
+            usize::MAX
+        };
+
+        let (value, raw) = if let Some(len) = len {
+            let end = header_len
+                .checked_add(len)
+                .ok_or_else(|| located(ErrorKind::TruncatedInput.into()))?;
+            if end > max_len {
+                return Err(located(ErrorKind::LimitExceeded.into()));
+            }
+            if end > input.len() {
+                return Err(located(ErrorKind::TruncatedInput.into()));
+            }
+            (&input[header_len..end], &input[..end])
+        } else {
+            if !tag.is_constructed() {
+                return Err(located(ErrorKind::InvalidLength.into()));
+            }
+            // content ends with end-of-contents bytes '00 00', and can only be delimited by parsing it
+            let mut end = header_len;
+            let mut children = 0;
+            loop {
+                if options.padding != Padding::Reject {
+                    end += input[end..].iter().take_while(|&&b| b == 0xFF).count();
+                }
+                if input.get(end) == Some(&0) {
+                    break;
+                }
+                children += 1;
+                if children > options.max_children {
+                    return Err(located(ErrorKind::LimitExceeded.into()));
+                }
+                let child = Self::read(&input[end..], offset + end, depth + 1, options)?;
+                end += child.raw.len();
+                if end > max_len {
+                    return Err(located(ErrorKind::LimitExceeded.into()));
+                }
+            }
+            // end-of-contents
+            if end + 2 > max_len {
+                return Err(located(ErrorKind::LimitExceeded.into()));
+            }
+            match input.get(end + 1) {
+                Some(0) => (),
+                Some(_) => return Err(located(ErrorKind::InvalidInput.into())),
+                None => return Err(located(ErrorKind::TruncatedInput.into())),
+            }
+            (&input[header_len..end], &input[..end + 2])
+        };
+        let length_form = if len.is_some() {
+            LengthForm::Definite
+        } else {
+            LengthForm::Indefinite
+        };
+        Ok(Self {
+            tag,
+            value,
+            raw,
+            length_form,
+            padding: options.padding,
+            original_lengths: options.original_lengths,
+        })
+    }
+
+    pub(crate) fn read(
+        input: &'a [u8],
+        offset: usize,
+        depth: usize,
+        options: &ParseOptions,
+    ) -> Result<Self> {
+        let ret = Self::read_unchecked(input, offset, depth, options)?;
+        if ret.tag.is_constructed() && ret.length_form == LengthForm::Definite {
+            let header_len = ret.raw.len() - ret.value.len();
+            let mut pos = 0;
+            let mut children = 0;
+            while pos < ret.value.len() {
+                if options.padding != Padding::Reject {
+                    pos += padding_len(&ret.value[pos..]);
+                    if pos == ret.value.len() {
+                        break;
+                    }
+                }
+                children += 1;
+                if children > options.max_children {
+                    let e = TlvError::from(ErrorKind::LimitExceeded);
+                    return Err(e.located(offset, Some(&ret.tag), depth));
+                }
+                let child_offset = offset + header_len + pos;
+                let child = Self::read(&ret.value[pos..], child_offset, depth + 1, options)
+                    .map_err(|e| match e.kind() {
+                        // a child exceeding its parent value is an inconsistency, not a truncation
+                        ErrorKind::TruncatedInput => e.with_kind(ErrorKind::Inconsistant),
+                        _ => e,
+                    })?;
+                pos += child.raw.len();
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Parses a byte array into a borrowed BER-TLV structure.
+    /// This also returns the unprocessed data, that is the whole input on error.
+    pub fn parse(input: &'a [u8]) -> (Result<Self>, &'a [u8]) {
+        Self::parse_with(input, &ParseOptions::new())
+    }
+
+    /// Parses a byte array into a borrowed BER-TLV structure, with given options.
+    /// This also returns the unprocessed data, that is the whole input on error.
+    /// Unless [`Padding::Reject`] is used, padding bytes following the data object are not returned.
+    pub fn parse_with(input: &'a [u8], options: &ParseOptions) -> (Result<Self>, &'a [u8]) {
+        let skip_padding = options.padding != Padding::Reject;
+        let start = if skip_padding { padding_len(input) } else { 0 };
+        match Self::read(&input[start..], start, 0, options) {
+            Ok(tlv) => {
+                let mut rest = &input[start + tlv.raw.len()..];
+                if skip_padding {
+                    rest = &rest[padding_len(rest)..];
+                }
+                (Ok(tlv), rest)
+            }
+            Err(e) => (Err(e), input),
+        }
+    }
+
+    /// Parses a byte array into a borrowed BER-TLV structure,
+    /// only accepting the Distinguished Encoding Rules (DER), see [`ParseOptions::der()`].
+    /// This also returns the unprocessed data, that is the whole input on error.
+    pub fn parse_der(input: &'a [u8]) -> (Result<Self>, &'a [u8]) {
+        Self::parse_with(input, &ParseOptions::der())
+    }
+
+    /// Parses a byte array into a borrowed BER-TLV structure.
+    /// Input must exactly match a BER-TLV object.
+    /// # Errors
+    /// Fails if input is not valid BER-TLV,
+    /// or with [`ErrorKind::InvalidInput`] if data remains after the BER-TLV object.
+    pub fn from_bytes(input: &'a [u8]) -> Result<Self> {
+        let (r, n) = Self::parse(input);
+        let tlv = r?;
+        if n.is_empty() {
+            Ok(tlv)
+        } else {
+            Err(ErrorKind::InvalidInput.into())
+        }
+    }
+
+    /// Finds the first data object with given tag in self, in document order.
+    /// Self and its descendants are searched, constructed or primitive.
+    #[must_use]
+    pub fn find(&self, tag: &Tag) -> Option<Self> {
+        self.find_by(|x| x.tag == *tag)
+    }
+
+    /// Finds the first data object of self and its descendants, in document order,
+    /// for which `predicate` returns `true`.
+    pub fn find_by<P>(&self, mut predicate: P) -> Option<Self>
+    where
+        P: FnMut(&Self) -> bool,
+    {
+        self.find_by_ref(&mut predicate)
+    }
+
+    fn find_by_ref<P>(&self, predicate: &mut P) -> Option<Self>
+    where
+        P: FnMut(&Self) -> bool,
+    {
+        if predicate(self) {
+            Some(self.clone())
+        } else {
+            self.children().find_map(|x| x.find_by_ref(predicate))
+        }
+    }
+}
+
+impl PartialEq for TlvRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.tag == other.tag && self.value == other.value
+    }
+}
+
+/// Iterator over the children of a constructed [`TlvRef`].
+///
+/// See [`TlvRef::children()`].
+#[derive(Debug, Clone)]
+pub struct Children<'a> {
+    rest: &'a [u8],
+    padding: Padding,
+    original_lengths: bool,
+}
+
+impl<'a> Children<'a> {
+    // skips padding bytes preceding the next child, if allowed when parsing the parent
+    pub(crate) fn take_padding(&mut self) -> &'a [u8] {
+        let n = match self.padding {
+            Padding::Reject => 0,
+            Padding::Skip | Padding::Preserve => padding_len(self.rest),
+        };
+        let (padding, rest) = self.rest.split_at(n);
+        self.rest = rest;
+        padding
+    }
+}
+
+impl<'a> Iterator for Children<'a> {
+    type Item = TlvRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.take_padding();
+        if self.rest.is_empty() {
+            return None;
+        }
+        // content has already been checked when parsing the parent
+        let options = ParseOptions::UNLIMITED
+            .padding(self.padding)
+            .original_lengths(self.original_lengths);
+        let ret = TlvRef::read_unchecked(self.rest, 0, 0, &options).ok()?;
+        self.rest = &self.rest[ret.raw.len()..];
+        Some(ret)
+    }
+}
+
+/// Lazy iterator over consecutive BER-TLV data objects of a byte array.
+///
+/// Each item is parsed on demand. When an object fails to parse, the error is
+/// yielded and iteration stops; [`offset()`](Self::offset()) then gives the position
+/// of the faulty object in the input, and iteration may go on using
+/// [`resume_at()`](Self::resume_at()).
+///
+/// # Example
+/// ```rust
+/// use iso7816_tlv::ber::TlvIter;
+/// # use iso7816_tlv::{ErrorKind, TlvError};
+///
+/// # fn main() -> Result<(), TlvError> {
+/// let data = [0x80, 0x01, 0x01, 0xA1, 0x02, 0x80, 0x01, 0x02, 0x81, 0x01, 0x02];
+/// let mut iter = TlvIter::new(&data);
+/// assert_eq!(&[0x01], iter.next().ok_or(ErrorKind::InvalidInput)??.value());
+///
+/// // in A1 02 80 01 02, the child does not fit in its parent
+/// let err = iter.next().and_then(Result::err).ok_or(ErrorKind::InvalidInput)?;
+/// assert_eq!(ErrorKind::Inconsistant, err.kind());
+/// assert_eq!((Some(5), 1), (err.offset(), err.depth()));
+/// assert_eq!(3, iter.offset());
+/// assert_eq!(None, iter.next());
+///
+/// // skip the faulty bytes, the last object can then be read
+/// iter.resume_at(8);
+/// assert_eq!(&[0x02], iter.next().ok_or(ErrorKind::InvalidInput)??.value());
+/// assert_eq!(None, iter.next());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TlvIter<'a> {
+    input: &'a [u8],
+    offset: usize,
+    stopped: bool,
+    options: ParseOptions,
+}
+
+impl<'a> TlvIter<'a> {
+    /// Creates an iterator over the BER-TLV data objects in `input`.
+    #[must_use]
+    pub fn new(input: &'a [u8]) -> Self {
+        Self::with_options(input, ParseOptions::new())
+    }
+
+    /// Creates an iterator over the BER-TLV data objects in `input`,
+    /// parsed with given options.
+    #[must_use]
+    pub fn with_options(input: &'a [u8], options: ParseOptions) -> Self {
+        Self {
+            input,
+            offset: 0,
+            stopped: false,
+            options,
+        }
+    }
+
+    /// Offset in the input of the next data object to parse.
+    /// After an error, this is the offset of the data object that failed to parse.
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Unprocessed part of the input.
+    #[must_use]
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.input[self.offset..]
+    }
+
+    /// Restarts iteration at the given offset of the input,
+    /// for instance to skip over a faulty data object.
+    /// Offsets past the end of input are clamped to the input length.
+    pub fn resume_at(&mut self, offset: usize) {
+        self.offset = offset.min(self.input.len());
+        self.stopped = false;
+    }
+}
+
+impl<'a> Iterator for TlvIter<'a> {
+    type Item = Result<TlvRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stopped {
+            return None;
+        }
+        if self.options.padding != Padding::Reject {
+            self.offset += padding_len(self.remaining());
+        }
+        if self.offset >= self.input.len() {
+            return None;
+        }
+        match TlvRef::read(self.remaining(), self.offset, 0, &self.options) {
+            Ok(tlv) => {
+                self.offset += tlv.as_bytes().len();
+                Some(Ok(tlv))
+            }
+            Err(e) => {
+                self.stopped = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+// number of '00' or 'FF' padding bytes at the beginning of input
+pub(super) fn padding_len(input: &[u8]) -> usize {
+    input
+        .iter()
+        .take_while(|&&b| b == 0x00 || b == 0xFF)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "alloc")]
+    use crate::ber::Value;
+    use alloc::vec::Vec;
+    use core::convert::TryFrom;
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn parse() -> Result<()> {
+        let input = hex!("7F22 09 010100 010100 010100 0101");
+        let (tlv, left) = TlvRef::parse(&input);
+        let tlv = tlv?;
+        assert_eq!(&hex!("0101"), left);
+        assert_eq!(&Tag::try_from("7f22")?, tlv.tag());
+        assert_eq!(12, tlv.length());
+        assert_eq!(&input[3..12], tlv.value());
+        assert_eq!(&input[..12], tlv.as_bytes());
+        assert_eq!(3, tlv.children().count());
+        for c in tlv.children() {
+            assert_eq!(&hex!("010100"), c.as_bytes());
+            assert_eq!(0, c.children().count());
+        }
+        assert_eq!(Tlv::parse(&input).0?, tlv.to_tlv());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_long_length() -> Result<()> {
+        let mut input = vec![0x01_u8, 0x82, 0x01, 0x00];
+        input.extend(&[0xAA_u8; 256]);
+        let tlv = TlvRef::from_bytes(&input)?;
+        assert_eq!(260, tlv.length());
+        assert_eq!(256, tlv.value().len());
+        assert_eq!(&input[4..], tlv.value());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_options() -> Result<()> {
+        let kind = |input: &[u8], options: &ParseOptions| {
+            TlvRef::parse_with(input, options)
+                .0
+                .map(|t| t.as_bytes().len())
+                .map_err(|e| e.kind())
+        };
+        let lenient = ParseOptions::new();
+        let strict = ParseOptions::strict();
+
+        // non-minimal lengths
+        for input in [&hex!("5A 81 01 00")[..], &hex!("5A 82 0001 00")] {
+            assert!(kind(input, &lenient).is_ok());
+            assert_eq!(Err(ErrorKind::InvalidLength), kind(input, &strict));
+        }
+        let mut input = vec![0x5A, 0x81, 0x80];
+        input.extend(&[0; 0x80]);
+        assert_eq!(Ok(0x83), kind(&input, &strict));
+
+        // non ISO7816-4 compliant tags, at any depth
+        for input in [&hex!("7F00 00")[..], &hex!("70 03 9F1E 00")] {
+            assert!(kind(input, &lenient).is_ok());
+            assert_eq!(Err(ErrorKind::InvalidInput), kind(input, &strict));
+        }
+
+        let input = hex!("70 0A 61 04 4F 02 A001 61 02 4F 00");
+        let limited = |options: ParseOptions| kind(&input, &options);
+        assert_eq!(Ok(12), limited(lenient.max_depth(2)));
+        assert_eq!(
+            Err(ErrorKind::DepthLimitExceeded),
+            limited(lenient.max_depth(1))
+        );
+        assert_eq!(Ok(12), limited(lenient.max_children(2)));
+        assert_eq!(
+            Err(ErrorKind::LimitExceeded),
+            limited(lenient.max_children(1))
+        );
+        assert_eq!(Ok(12), limited(lenient.max_len(12)));
+        assert_eq!(Err(ErrorKind::LimitExceeded), limited(lenient.max_len(11)));
+        // limits are also checked in the indefinite length form
+        let input = hex!("70 80 61 80 4F 02 A001 0000 61 02 4F 00 0000");
+        let limited = |options: ParseOptions| kind(&input, &options);
+        assert_eq!(
+            Ok(16),
+            limited(lenient.max_depth(2).max_children(2).max_len(16))
+        );
+        assert_eq!(
+            Err(ErrorKind::DepthLimitExceeded),
+            limited(lenient.max_depth(1))
+        );
+        assert_eq!(
+            Err(ErrorKind::LimitExceeded),
+            limited(lenient.max_children(1))
+        );
+        assert_eq!(Err(ErrorKind::LimitExceeded), limited(lenient.max_len(15)));
+        // claimed length is checked before the input length
+        assert_eq!(
+            Err(ErrorKind::LimitExceeded),
+            kind(&hex!("5A 84 FFFFFFFF"), &lenient.max_len(1024))
+        );
+
+        // padding
+        let input = hex!("00 FF 80 01 01 00 00 81 00 FF");
+        let padded = lenient;
+        assert_eq!(
+            Err(ErrorKind::InvalidInput),
+            TlvIter::with_options(&input, lenient.padding(Padding::Reject))
+                .next()
+                .ok_or(ErrorKind::InvalidInput)?
+                .map_err(|e| e.kind())
+        );
+        let tlvs = TlvIter::with_options(&input, padded).collect::<Result<Vec<_>>>()?;
+        assert_eq!(2, tlvs.len());
+        assert_eq!(&[0x81, 0x00], tlvs[1].as_bytes());
+        // parsing options are not compared
+        let preserved = TlvRef::parse_with(&input[7..], &lenient.padding(Padding::Preserve)).0;
+        assert_eq!(tlvs[1], preserved?);
+        let (tlv, rest) = TlvRef::parse_with(&input, &padded);
+        assert_eq!(&[0x80, 0x01, 0x01], tlv?.as_bytes());
+        assert_eq!(&input[7..], rest);
+        let e = TlvIter::with_options(&hex!("FF 5A 05"), padded)
+            .find_map(Result::err)
+            .ok_or(ErrorKind::InvalidInput)?;
+        assert_eq!(Some(1), e.offset());
+        Ok(())
+    }
+
+    // hostile inputs, nesting constructed data objects far deeper than the default limit
+    fn nested_corpus(levels: usize) -> Vec<Vec<u8>> {
+        let mut corpus = Vec::new();
+        // indefinite length, without end-of-contents
+        corpus.push([0x20, 0x80].repeat(levels));
+        // indefinite length, terminated
+        let mut input = [0x20, 0x80].repeat(levels);
+        input.resize(input.len() + 2 * levels, 0);
+        corpus.push(input);
+        // definite length, each level holding the next one
+        let mut input = Vec::new();
+        for _ in 0..levels {
+            input.splice(..0, definite_header(input.len()));
+        }
+        corpus.push(input);
+        // both length forms
+        let mut input = Vec::new();
+        for level in 0..levels {
+            if level % 2 == 0 {
+                input.splice(..0, definite_header(input.len()));
+            } else {
+                input.splice(..0, [0x20, 0x80]);
+                input.extend(&[0, 0]);
+            }
+        }
+        corpus.push(input);
+        corpus
+    }
+
+    fn definite_header(len: usize) -> Vec<u8> {
+        let len = len.to_be_bytes();
+        let mut header = vec![0x7F, 0x22, 0x83];
+        header.extend(&len[len.len() - 3..]);
+        header
+    }
+
+    #[test]
+    fn depth_limit() -> Result<()> {
+        for input in nested_corpus(10_000) {
+            let e = TlvRef::parse(&input)
+                .0
+                .err()
+                .ok_or(ErrorKind::InvalidInput)?;
+            assert_eq!(ErrorKind::DepthLimitExceeded, e.kind());
+            assert_eq!(ParseOptions::DEFAULT_MAX_DEPTH + 1, e.depth());
+
+            let e = TlvIter::new(&input)
+                .find_map(Result::err)
+                .ok_or(ErrorKind::InvalidInput)?;
+            assert_eq!(ErrorKind::DepthLimitExceeded, e.kind());
+
+            let options = ParseOptions::new().max_depth(8);
+            let e = TlvRef::parse_with(&input, &options)
+                .0
+                .err()
+                .ok_or(ErrorKind::InvalidInput)?;
+            assert_eq!((ErrorKind::DepthLimitExceeded, 9), (e.kind(), e.depth()));
+
+            #[cfg(feature = "alloc")]
+            {
+                use crate::ber::Decoder;
+
+                assert_eq!(
+                    Err(ErrorKind::DepthLimitExceeded),
+                    Tlv::from_bytes(&input).map_err(|e| e.kind())
+                );
+                let mut decoder = Decoder::new();
+                decoder.push(&input);
+                assert_eq!(
+                    Err(ErrorKind::DepthLimitExceeded),
+                    decoder.decode().map_err(|e| e.kind())
+                );
+            }
+        }
+
+        // up to the limit, data objects are parsed and can be walked through
+        let levels = ParseOptions::DEFAULT_MAX_DEPTH + 1;
+        for input in nested_corpus(levels).iter().skip(1) {
+            let mut tlv = TlvRef::from_bytes(input)?;
+            for _ in 1..levels {
+                tlv = tlv.children().next().ok_or(ErrorKind::InvalidInput)?;
+            }
+        }
+        for input in nested_corpus(levels + 1).iter().skip(1) {
+            assert!(TlvRef::from_bytes(input).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn fuzz() {
+        use rand_core::{RngCore, SeedableRng};
+
+        // bytes most likely to build (deeply nested) headers
+        const ALPHABET: [u8; 8] = [0x00, 0x01, 0x20, 0x3F, 0x80, 0x81, 0xA1, 0xFF];
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(0x7816);
+        let mut input = [0_u8; 512];
+        for _ in 0..2000 {
+            for b in &mut input {
+                *b = ALPHABET[rng.next_u32() as usize % ALPHABET.len()];
+            }
+            for tlv in TlvIter::new(&input).map_while(Result::ok) {
+                assert!(tlv.find_by(|_| false).is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn error_location() -> Result<()> {
+        use alloc::string::ToString;
+
+        // 87 03 overflows A5
+        let input = hex!("6F 0C 84 02 A000 A5 06 50 01 41 87 03 01");
+        let e = TlvRef::parse(&input)
+            .0
+            .err()
+            .ok_or(ErrorKind::InvalidInput)?;
+        assert_eq!(ErrorKind::Inconsistant, e.kind());
+        assert_eq!(Some(11), e.offset());
+        assert_eq!(Some(&Tag::try_from(0x87)?), e.tag());
+        assert_eq!(2, e.depth());
+        assert!(e
+            .to_string()
+            .ends_with("at offset 11 (Tag 87 (ContextSpecific)), depth 2"));
+
+        // missing end-of-contents: next child cannot be read
+        let e = TlvRef::parse(&hex!("A1 80 0101AA"))
+            .0
+            .err()
+            .ok_or(ErrorKind::InvalidInput)?;
+        assert_eq!(ErrorKind::TruncatedInput, e.kind());
+        assert_eq!((Some(5), None, 1), (e.offset(), e.tag(), e.depth()));
+
+        // iterator offsets are relative to its whole input
+        let input = hex!("80 01 01 A1 02 80 01 02");
+        let e = TlvIter::new(&input)
+            .find_map(Result::err)
+            .ok_or(ErrorKind::InvalidInput)?;
+        assert_eq!((Some(5), 1), (e.offset(), e.depth()));
+
+        // errors not raised by parsing are not located
+        let e = TlvRef::from_bytes(&hex!("01 00 01"))
+            .err()
+            .ok_or(ErrorKind::InvalidInput)?;
+        assert_eq!((ErrorKind::InvalidInput, None), (e.kind(), e.offset()));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_errors() {
+        // truncated value
+        assert_eq!(
+            Err(ErrorKind::TruncatedInput),
+            TlvRef::parse(&hex!("01 03 0000")).0.map_err(|e| e.kind())
+        );
+        // child longer than its parent
+        assert_eq!(
+            Err(ErrorKind::Inconsistant),
+            TlvRef::parse(&hex!("7F22 03 01 02 00"))
+                .0
+                .map_err(|e| e.kind())
+        );
+        // trailing data
+        assert_eq!(
+            Err(ErrorKind::InvalidInput),
+            TlvRef::from_bytes(&hex!("01 01 00 01")).map_err(|e| e.kind())
+        );
+    }
+
+    #[test]
+    fn parse_indefinite_length() -> Result<()> {
+        let input = hex!("7F22 80 010100 A1 80 0101AA 0000 0000 0101");
+        let (tlv, left) = TlvRef::parse(&input);
+        let tlv = tlv?;
+        assert_eq!(&hex!("0101"), left);
+        assert_eq!(LengthForm::Indefinite, tlv.length_form());
+        assert_eq!(&input[..input.len() - 2], tlv.as_bytes());
+        assert_eq!(&input[3..13], tlv.value());
+
+        let children: Vec<TlvRef> = tlv.children().collect();
+        assert_eq!(2, children.len());
+        assert_eq!(LengthForm::Definite, children[0].length_form());
+        assert_eq!(LengthForm::Indefinite, children[1].length_form());
+        assert_eq!(&hex!("0101AA"), children[1].value());
+        assert_eq!(&input[6..13], children[1].as_bytes());
+
+        // indefinite length on primitive data objects
+        assert_eq!(
+            Err(ErrorKind::InvalidLength),
+            TlvRef::parse(&hex!("01 80 01 0000"))
+                .0
+                .map_err(|e| e.kind())
+        );
+        // missing end-of-contents
+        assert_eq!(
+            Err(ErrorKind::TruncatedInput),
+            TlvRef::parse(&hex!("A1 80 0101AA")).0.map_err(|e| e.kind())
+        );
+        // end-of-contents inside definite length parent
+        assert_eq!(
+            Err(ErrorKind::Inconsistant),
+            TlvRef::parse(&hex!("A1 05 A2 80 0100 00 00"))
+                .0
+                .map_err(|e| e.kind())
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn find() -> Result<()> {
+        let input = hex!("6F 0C 84 02 A000 A5 06 50 01 41 87 01 01");
+        let tlv = TlvRef::from_bytes(&input)?;
+        let found = tlv
+            .find(&Tag::try_from(0x87)?)
+            .ok_or(ErrorKind::InvalidInput)?;
+        assert_eq!(&hex!("01"), found.value());
+        assert!(tlv.find(&Tag::try_from(0x88)?).is_none());
+        let template = tlv
+            .find(&Tag::try_from(0xA5)?)
+            .ok_or(ErrorKind::InvalidInput)?;
+        assert_eq!(&input[6..], template.as_bytes());
+        let last = tlv
+            .find_by(|x| x.value() == [0x01])
+            .ok_or(ErrorKind::InvalidInput)?;
+        assert_eq!(&Tag::try_from(0x87)?, last.tag());
+
+        let owned = tlv.to_tlv();
+        let expected = Tlv::new(Tag::try_from(0x87)?, Value::Primitive(vec![1]))?;
+        assert_eq!(Some(&expected), owned.find(&Tag::try_from(0x87)?));
+        assert_eq!(input.to_vec(), owned.to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn encode() -> Result<()> {
+        let input = hex!("6F 05 84 03 A00000 9000");
+        let (tlv, _) = TlvRef::parse(&input);
+        let tlv = tlv?;
+        let mut buf = [0_u8; 8];
+        assert_eq!(7, tlv.encode_into(&mut buf)?);
+        assert_eq!(&input[..7], &buf[..7]);
+        assert_eq!(
+            Err(ErrorKind::BufferTooSmall),
+            tlv.encode_into(&mut buf[..6]).map_err(|e| e.kind())
+        );
+
+        let mut sink = &mut buf[..];
+        tlv.children().try_for_each(|c| c.encode_to(&mut sink))?;
+        assert_eq!(3, sink.len());
+        assert_eq!(
+            Err(ErrorKind::WriteError),
+            tlv.encode_to(&mut sink).map_err(|e| e.kind())
+        );
+        assert_eq!(&input[2..7], &buf[..5]);
+        Ok(())
+    }
+
+    #[test]
+    fn build() -> Result<()> {
+        let mut buf = [0_u8; 300];
+        let value = [0xAA; 200];
+        let tlv = TlvRef::new(&Tag::try_from(0x84)?, &value, &mut buf)?;
+        assert_eq!(&hex!("84 81 C8"), &tlv.as_bytes()[..3]);
+        assert_eq!(&value[..], tlv.value());
+
+        let mut child_buf = [0_u8; 8];
+        let child = TlvRef::new(&Tag::try_from(0x84)?, &hex!("A00000"), &mut child_buf)?;
+        let mut buf = [0_u8; 8];
+        let tlv = TlvRef::new(&Tag::try_from(0x6F)?, child.as_bytes(), &mut buf)?;
+        assert_eq!(&hex!("6F 05 84 03 A00000"), tlv.as_bytes());
+        assert_eq!(Some(child), tlv.children().next());
+
+        assert_eq!(
+            Err(ErrorKind::BufferTooSmall),
+            TlvRef::new(&Tag::try_from(0x84)?, &value, &mut buf).map_err(|e| e.kind())
+        );
+        // constructed value must be BER-TLV encoded
+        assert_eq!(
+            Err(ErrorKind::Inconsistant),
+            TlvRef::new(&Tag::try_from(0x6F)?, &hex!("84 03 A0"), &mut buf).map_err(|e| e.kind())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn iter() -> Result<()> {
+        let input = hex!("80 01 01 A1 03 80 01 02 81 00");
+        let parsed: Result<Vec<TlvRef>> = TlvIter::new(&input).collect();
+        let parsed = parsed?;
+        assert_eq!(3, parsed.len());
+        assert_eq!(&input[3..8], parsed[1].as_bytes());
+        assert_eq!(&input[8..], parsed[2].as_bytes());
+        assert_eq!(0, TlvIter::new(&[]).count());
+        Ok(())
+    }
+
+    #[test]
+    fn iter_error_and_resume() -> Result<()> {
+        // second object is inconsistent: its child overflows it
+        let input = hex!("80 01 01 A1 02 80 01 02 81 00");
+        let mut iter = TlvIter::new(&input);
+        assert!(iter.next().is_some());
+        assert_eq!(
+            Some(Err(ErrorKind::Inconsistant)),
+            iter.next().map(|r| r.map_err(|e| e.kind()))
+        );
+        assert_eq!(3, iter.offset());
+        assert_eq!(&input[3..], iter.remaining());
+        assert_eq!(None, iter.next());
+
+        iter.resume_at(8);
+        let last = iter.next().ok_or(ErrorKind::InvalidInput)??;
+        assert_eq!(&Tag::try_from(0x81)?, last.tag());
+        assert_eq!(None, iter.next());
+        assert_eq!(input.len(), iter.offset());
+
+        iter.resume_at(100);
+        assert_eq!(None, iter.next());
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
 use super::Tlv;
-use crate::error::TlvError;
+use crate::ErrorKind;
 use crate::Result;
 
 use alloc::vec::Vec;
@@ -26,20 +26,70 @@ impl Value {
     pub fn len_as_bytes(&self) -> usize {
         match &self {
             Self::Primitive(v) => v.len(),
-            Self::Constructed(tlv) => tlv.iter().fold(0, |sum, x| sum + x.len()),
+            Self::Constructed(tlv) => tlv
+                .iter()
+                .fold(0, |sum, x| sum + x.encoded_len_with(false, None)),
         }
     }
 
     /// Append a BER-TLV data object.
     /// # Errors
-    /// Fails with `TlvError::Inconsistant` on primitive or empty values.
+    /// Fails with `ErrorKind::Inconsistant` on primitive or empty values.
     pub fn push(&mut self, tlv: Tlv) -> Result<()> {
         match self {
             Self::Constructed(t) => {
                 t.push(tlv);
                 Ok(())
             }
-            Self::Primitive(_) => Err(TlvError::Inconsistant),
+            Self::Primitive(_) => Err(ErrorKind::Inconsistant.into()),
         }
     }
 }
+
+/// Serialized as a hex string for primitive values,
+/// and as a list of data objects for constructed values.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Value {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> core::result::Result<S::Ok, S::Error> {
+        match self {
+            Self::Primitive(v) => crate::hex::Hex(v).serialize(s),
+            Self::Constructed(tlv) => s.collect_seq(tlv),
+        }
+    }
+}
+
+/// As the representation of the value depends on its form,
+/// deserialization requires a self-describing format, such as JSON.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Value {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> core::result::Result<Self, D::Error> {
+        d.deserialize_any(ValueVisitor)
+    }
+}
+
+#[cfg(feature = "serde")]
+struct ValueVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("a hex string or a list of data objects")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> core::result::Result<Value, E> {
+        crate::hex::BytesVisitor.visit_str(v).map(Value::Primitive)
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> core::result::Result<Value, A::Error> {
+        let mut tlv = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(16));
+        while let Some(t) = seq.next_element()? {
+            tlv.push(t);
+        }
+        Ok(Value::Constructed(tlv))
+    }
+}
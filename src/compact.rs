@@ -0,0 +1,286 @@
+//! This module provides tools and utilities for handling COMPACT-TLV data as
+//! defined in [ISO7816-4][iso7816-4].
+//!
+//! COMPACT-TLV data objects are mostly found in the historical bytes of an
+//! Answer-to-Reset (ATR).
+//!
+//!
+//! [iso7816-4]: https://www.iso.org/standard/54550.html
+//!
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+#[cfg(feature = "alloc")]
+use untrusted::{Input, Reader};
+
+use crate::{ErrorKind, Result, TlvError};
+
+/// Tag for COMPACT-TLV data as defined in [ISO7816-4].
+/// > The first byte of a COMPACT-TLV data object consists of two nibbles:
+/// > the first nibble encodes the tag number, the second nibble the number of
+/// > bytes of the value field.
+///
+/// Tags can be generated using the [`TryFrom`][TryFrom] trait
+/// from u8 or hex [str][str], the tag number being from 1 to 15.
+/// Tags defined in ISO/IEC 7816-4 are available as constants.
+///
+/// [TryFrom]: https://doc.rust-lang.org/std/convert/trait.TryFrom.html
+/// [str]:https://doc.rust-lang.org/std/str/
+///
+/// # Example
+/// ```rust
+/// use std::convert::TryFrom;
+/// use iso7816_tlv::compact::Tag;
+/// # use iso7816_tlv::TlvError;
+/// # fn main() -> Result<(), TlvError> {
+///
+/// // get tag from u8 or &str
+/// assert!(Tag::try_from("3").is_ok());
+/// assert!(Tag::try_from(7u8).is_ok());
+/// assert_eq!(Tag::CARD_CAPABILITIES, Tag::try_from(7u8)?);
+///
+/// assert!(Tag::try_from("er").is_err());
+/// assert!(Tag::try_from(0u8).is_err());
+/// assert!(Tag::try_from(0x10u8).is_err());
+///
+/// // get tag number as u8
+/// let tag_number: u8 = Tag::APPLICATION_IDENTIFIER.into();
+/// assert_eq!(0xF, tag_number);
+/// # Ok(())
+/// # }
+/// #
+/// ```
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Tag(u8);
+
+impl Tag {
+    /// Country code, ISO 3166-1 (tag '1')
+    pub const COUNTRY_CODE: Self = Self(0x1);
+    /// Issuer identification number, ISO/IEC 7812-1 (tag '2')
+    pub const ISSUER_IDENTIFICATION: Self = Self(0x2);
+    /// Card service data (tag '3')
+    pub const CARD_SERVICE_DATA: Self = Self(0x3);
+    /// Initial access data (tag '4')
+    pub const INITIAL_ACCESS_DATA: Self = Self(0x4);
+    /// Card issuer's data (tag '5')
+    pub const CARD_ISSUER_DATA: Self = Self(0x5);
+    /// Pre-issuing data (tag '6')
+    pub const PRE_ISSUING_DATA: Self = Self(0x6);
+    /// Card capabilities (tag '7')
+    pub const CARD_CAPABILITIES: Self = Self(0x7);
+    /// Status indicator (tag '8')
+    pub const STATUS_INDICATOR: Self = Self(0x8);
+    /// Application identifier (tag 'F')
+    pub const APPLICATION_IDENTIFIER: Self = Self(0xF);
+
+    /// Tries to convert a tag number into a `Tag`. This is equivalent to the
+    /// [`TryFrom`] impl for `u8`, except that this fn is const and can be used
+    /// for defining constants.
+    ///
+    /// # Errors
+    /// This method returns `Err(ErrorKind::InvalidInput.into())` if `v` is not a legal
+    /// tag number (i.e., if v is `0x00` or greater than `0x0F`).
+    pub const fn try_from_u8(v: u8) -> Result<Self> {
+        match v {
+            0x01..=0x0F => Ok(Self(v)),
+            _ => Err(TlvError::new(ErrorKind::InvalidInput)),
+        }
+    }
+}
+
+// From impl may fail, not the converse
+#[allow(clippy::from_over_into)]
+impl Into<u8> for Tag {
+    fn into(self) -> u8 {
+        self.0
+    }
+}
+
+impl TryFrom<u8> for Tag {
+    type Error = TlvError;
+    fn try_from(v: u8) -> Result<Self> {
+        Self::try_from_u8(v)
+    }
+}
+
+impl TryFrom<&str> for Tag {
+    type Error = TlvError;
+    fn try_from(v: &str) -> Result<Self> {
+        let x = u8::from_str_radix(v, 16)?;
+        Self::try_from(x)
+    }
+}
+
+/// Value for COMPACT-TLV data as defined in [ISO7816-4].
+/// > The value field consists of N consecutive bytes, N being from 0 to 15.
+///
+/// In case N is zero, Value is an empty vector
+#[cfg(feature = "alloc")]
+pub type Value = Vec<u8>;
+
+/// COMPACT-TLV data object representation.
+/// > Each COMPACT-TLV data object consists of a one-byte header
+/// > (tag number and length) followed by a conditional value field
+#[cfg(feature = "alloc")]
+#[derive(PartialEq, Debug, Clone)]
+pub struct Tlv {
+    tag: Tag,
+    value: Value,
+}
+
+#[cfg(feature = "alloc")]
+impl Tlv {
+    const MAX_LEN: usize = 0x0F;
+
+    /// Create a COMPACT-TLV data object from valid tag and value.
+    /// A value has a maximum size of `15` bytes.
+    ///
+    /// # Errors
+    /// Fails with `ErrorKind::InvalidLength` if value is longer than `15` bytes.
+    pub fn new(tag: Tag, value: Value) -> Result<Self> {
+        if value.len() > Self::MAX_LEN {
+            Err(ErrorKind::InvalidLength.into())
+        } else {
+            Ok(Self { tag, value })
+        }
+    }
+
+    /// Get COMPACT-TLV tag.
+    #[must_use]
+    pub fn tag(&self) -> Tag {
+        self.tag
+    }
+
+    /// Get COMPACT-TLV value length
+    #[must_use]
+    pub fn length(&self) -> usize {
+        self.value.len()
+    }
+
+    /// Get COMPACT-TLV value
+    #[must_use]
+    pub fn value(&self) -> &[u8] {
+        self.value.as_slice()
+    }
+
+    /// serializes self into a byte vector.
+    #[allow(clippy::cast_possible_truncation)]
+    #[must_use]
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut ret = vec![self.tag.0 << 4 | self.value.len() as u8];
+        ret.extend(&self.value);
+        ret
+    }
+
+    fn read(r: &mut Reader) -> Result<Self> {
+        let header = r.read_byte()?;
+        let tag = Tag::try_from(header >> 4)?;
+        let content = r.read_bytes(usize::from(header & 0x0F))?;
+
+        Ok(Self {
+            tag,
+            value: content.as_slice_less_safe().to_vec(),
+        })
+    }
+
+    /// Parses a byte array into a COMPACT-TLV structure.
+    /// This also returns the unprocessed data.
+    pub fn parse(input: &[u8]) -> (Result<Self>, &[u8]) {
+        let mut r = Reader::new(Input::from(input));
+        (
+            Self::read(&mut r),
+            r.read_bytes_to_end().as_slice_less_safe(),
+        )
+    }
+
+    /// Parses a byte array into a vector of COMPACT-TLV.
+    /// # Note
+    /// Errors are discarded and parsing stops at first error
+    /// Prefer using the [`parse()`](Self::parse()) method and iterate over returned processed data.
+    #[must_use]
+    pub fn parse_all(input: &[u8]) -> Vec<Self> {
+        let mut ret = Vec::new();
+        let mut r = Reader::new(Input::from(input));
+        while !r.at_end() {
+            if Self::read(&mut r).map(|elem| ret.push(elem)).is_err() {
+                break;
+            }
+        }
+        ret
+    }
+
+    /// Parses a byte array into a COMPACT-TLV structure.
+    /// Input must exactly match a COMPACT-TLV object.
+    /// # Errors
+    /// Fails with `ErrorKind::InvalidInput` if input does not match a COMPACT-TLV object.
+    pub fn from_bytes(input: &[u8]) -> Result<Self> {
+        let (r, n) = Self::parse(input);
+        if n.is_empty() {
+            r
+        } else {
+            Err(ErrorKind::InvalidInput.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::TryFrom;
+
+    #[test]
+    fn tag_import() -> Result<()> {
+        assert!(Tag::try_from("1").is_ok());
+        assert_eq!(0xF_u8, Tag::try_from("f")?.into());
+        assert_eq!(Tag::STATUS_INDICATOR, Tag::try_from(8_u8)?);
+
+        assert!(Tag::try_from("er").is_err());
+        assert!(Tag::try_from("00").is_err());
+        assert!(Tag::try_from("10").is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn parse_all() -> Result<()> {
+        // card capabilities followed by card issuer data
+        let in_data = hex!("73 C8 21 10 52 00 00");
+        let (r, left) = Tlv::parse(&in_data);
+        let t = r?;
+        assert_eq!(Tag::CARD_CAPABILITIES, t.tag());
+        assert_eq!(&hex!("C8 21 10"), t.value());
+        assert_eq!(&hex!("52 00 00"), left);
+
+        let parsed = Tlv::parse_all(&in_data);
+        assert_eq!(2, parsed.len());
+        assert_eq!(Tag::CARD_ISSUER_DATA, parsed[1].tag());
+        assert_eq!(2, parsed[1].length());
+
+        // parsing stops at first error
+        assert_eq!(1, Tlv::parse_all(&hex!("31 80 04 00")).len());
+        assert_eq!(
+            Err(ErrorKind::TruncatedInput),
+            Tlv::parse(&hex!("43 00 00")).0.map_err(|e| e.kind())
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn serialize_parse() -> Result<()> {
+        for t in 1_u8..=0x0F {
+            for len in 0..=Tlv::MAX_LEN {
+                let tlv = Tlv::new(Tag::try_from(t)?, vec![t; len])?;
+                let ser = tlv.to_vec();
+                assert_eq!(len + 1, ser.len());
+                assert_eq!(tlv, Tlv::from_bytes(&ser)?);
+            }
+        }
+        assert_eq!(
+            Err(ErrorKind::InvalidLength),
+            Tlv::new(Tag::COUNTRY_CODE, vec![0; 16]).map_err(|e| e.kind())
+        );
+        Ok(())
+    }
+}
@@ -1,9 +1,12 @@
+use core::convert::TryFrom;
 use core::fmt;
+use core::num::NonZeroUsize;
 
-/// Error definition for TLV data as defined in [ISO7816-4].
-#[allow(clippy::module_name_repetitions)]
-#[derive(PartialEq, Clone, Debug)]
-pub enum TlvError {
+use crate::ber::Tag;
+
+/// Kind of error for TLV data as defined in [ISO7816-4].
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ErrorKind {
   /// Invalid input encountered
   InvalidInput,
   /// Read tag is reserved for future usage
@@ -16,18 +19,30 @@ pub enum TlvError {
   Inconsistant,
   /// Read invalid length value
   InvalidLength,
+  /// Check byte does not match data
+  InvalidChecksum,
+  /// Output buffer is too small for encoded data
+  BufferTooSmall,
+  /// Encoded data could not be written
+  WriteError,
+  /// Encoded data could not be read
+  ReadError,
+  /// Parsed data exceeds a limit set in parsing options
+  LimitExceeded,
+  /// Parsed data objects are nested deeper than allowed by parsing options
+  DepthLimitExceeded,
+  /// Data type cannot be represented in BER-TLV data
+  Unsupported,
+  /// A mandatory data object is missing
+  MissingField,
+  /// A data object is not expected here
+  UnexpectedField,
+  /// Error raised by a serde `Serialize` or `Deserialize` implementation,
+  /// whose message is displayed along with the error
+  Custom,
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for TlvError {
-  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-    match self {
-      _ => None,
-    }
-  }
-}
-
-impl fmt::Display for TlvError {
+impl fmt::Display for ErrorKind {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     let s = match self {
       Self::InvalidInput => "Invalid tag encountered",
@@ -36,19 +51,256 @@ impl fmt::Display for TlvError {
       Self::TruncatedInput => "Error input too short",
       Self::Inconsistant => "Inconsistant (tag, value) pair",
       Self::InvalidLength => "Read invalid length value",
+      Self::InvalidChecksum => "Check byte does not match data",
+      Self::BufferTooSmall => "Output buffer too small",
+      Self::WriteError => "Error writing encoded data",
+      Self::ReadError => "Error reading encoded data",
+      Self::LimitExceeded => "Parsing limit exceeded",
+      Self::DepthLimitExceeded => "Maximum nesting depth exceeded",
+      Self::Unsupported => "Unsupported data type",
+      Self::MissingField => "Missing data object",
+      Self::UnexpectedField => "Unexpected data object",
+      Self::Custom => "Serialization error",
     };
     write!(f, "{s}")
   }
 }
 
+/// Error definition for TLV data as defined in [ISO7816-4].
+///
+/// Besides its [`ErrorKind`], an error raised while parsing BER-TLV data
+/// tells where it happened: the offset in the input of the data object
+/// that failed to parse, its tag if it could be read, and its nesting depth
+/// (top-level data objects have a depth of 0).
+#[allow(clippy::module_name_repetitions)]
+#[derive(PartialEq, Clone, Debug)]
+pub struct TlvError {
+  kind: ErrorKind,
+  // offset plus one, so that the location takes no extra room
+  offset: Option<NonZeroUsize>,
+  tag: Option<Tag>,
+  // saturated, data nested that deep does not fit in memory anyway
+  depth: u32,
+  #[cfg(feature = "serde")]
+  detail: Option<Detail>,
+}
+
+// errors are kept small and without destructor (see `Tag::try_from_u32()`)
+#[cfg(feature = "serde")]
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum Detail {
+  // name of the field, variant or type involved
+  Name(&'static str),
+  // message of a custom serde error
+  Message(Message),
+}
+
+// custom serde error message, truncated to fit in the error
+#[cfg(feature = "serde")]
+#[derive(PartialEq, Clone, Copy)]
+struct Message {
+  buf: [u8; Self::CAPACITY],
+  len: u8,
+}
+
+#[cfg(feature = "serde")]
+impl Message {
+  const CAPACITY: usize = 38;
+
+  fn new<T: fmt::Display>(msg: T) -> Self {
+    let mut message = Self {
+      buf: [0; Self::CAPACITY],
+      len: 0,
+    };
+    // fails once truncated
+    let _ = fmt::Write::write_fmt(&mut message, format_args!("{msg}"));
+    message
+  }
+
+  fn as_str(&self) -> &str {
+    core::str::from_utf8(&self.buf[..usize::from(self.len)]).unwrap_or_default()
+  }
+}
+
+#[cfg(feature = "serde")]
+impl fmt::Write for Message {
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    let len = usize::from(self.len);
+    let mut n = s.len().min(Self::CAPACITY - len);
+    while !s.is_char_boundary(n) {
+      n -= 1;
+    }
+    self.buf[len..len + n].copy_from_slice(&s.as_bytes()[..n]);
+    self.len = u8::try_from(len + n).map_err(|_| fmt::Error)?;
+    if n == s.len() {
+      Ok(())
+    } else {
+      Err(fmt::Error)
+    }
+  }
+}
+
+#[cfg(feature = "serde")]
+impl fmt::Debug for Message {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Debug::fmt(self.as_str(), f)
+  }
+}
+
+impl TlvError {
+  pub(crate) const fn new(kind: ErrorKind) -> Self {
+    Self {
+      kind,
+      offset: None,
+      tag: None,
+      depth: 0,
+      #[cfg(feature = "serde")]
+      detail: None,
+    }
+  }
+
+  /// Get the kind of error
+  #[must_use]
+  pub fn kind(&self) -> ErrorKind {
+    self.kind
+  }
+
+  /// Get the offset in the input of the data object that failed to parse, if known
+  #[must_use]
+  pub fn offset(&self) -> Option<usize> {
+    self.offset.map(|o| o.get() - 1)
+  }
+
+  /// Get the tag of the data object that failed to parse, if it could be read
+  #[must_use]
+  pub fn tag(&self) -> Option<&Tag> {
+    self.tag.as_ref()
+  }
+
+  /// Get the nesting depth of the data object that failed to parse
+  #[must_use]
+  pub fn depth(&self) -> usize {
+    usize::try_from(self.depth).unwrap_or(usize::MAX)
+  }
+
+  // locates the error, unless already located by a nested data object
+  pub(crate) fn located(mut self, offset: usize, tag: Option<&Tag>, depth: usize) -> Self {
+    if self.offset.is_none() {
+      self.offset = offset.checked_add(1).and_then(NonZeroUsize::new);
+      self.tag = tag.cloned();
+      self.depth = u32::try_from(depth).unwrap_or(u32::MAX);
+    }
+    self
+  }
+
+  pub(crate) fn with_kind(mut self, kind: ErrorKind) -> Self {
+    self.kind = kind;
+    self
+  }
+
+  // tag of the data object involved, unless already set
+  #[cfg(feature = "alloc")]
+  pub(crate) fn with_tag(mut self, tag: &Tag) -> Self {
+    if self.tag.is_none() {
+      self.tag = Some(tag.clone());
+    }
+    self
+  }
+
+  // names the field, variant or type involved
+  #[cfg(feature = "serde")]
+  pub(crate) fn with_detail(mut self, detail: &'static str) -> Self {
+    self.detail = Some(Detail::Name(detail));
+    self
+  }
+}
+
+impl From<ErrorKind> for TlvError {
+  fn from(kind: ErrorKind) -> Self {
+    Self::new(kind)
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TlvError {}
+
+// serde errors must implement serde's own `Error` trait without `std`
+#[cfg(all(feature = "serde", not(feature = "std")))]
+impl serde::ser::StdError for TlvError {}
+
+impl fmt::Display for TlvError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.kind)?;
+    #[cfg(feature = "serde")]
+    match &self.detail {
+      Some(Detail::Name(name)) => write!(f, " ({name})")?,
+      Some(Detail::Message(message)) => write!(f, " ({})", message.as_str())?,
+      None => (),
+    }
+    if let Some(offset) = self.offset() {
+      write!(f, " at offset {offset}")?;
+      if let Some(tag) = &self.tag {
+        write!(f, " ({tag})")?;
+      }
+      write!(f, ", depth {}", self.depth)?;
+    } else if let Some(tag) = &self.tag {
+      write!(f, " ({tag})")?;
+    }
+    Ok(())
+  }
+}
+
 impl From<core::num::ParseIntError> for TlvError {
   fn from(_: core::num::ParseIntError) -> Self {
-    Self::ParseIntError
+    ErrorKind::ParseIntError.into()
+  }
+}
+
+impl From<core::convert::Infallible> for TlvError {
+  fn from(e: core::convert::Infallible) -> Self {
+    match e {}
+  }
+}
+
+impl From<core::array::TryFromSliceError> for TlvError {
+  fn from(_: core::array::TryFromSliceError) -> Self {
+    ErrorKind::InvalidLength.into()
   }
 }
 
 impl From<untrusted::EndOfInput> for TlvError {
   fn from(_: untrusted::EndOfInput) -> Self {
-    Self::TruncatedInput
+    ErrorKind::TruncatedInput.into()
+  }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for TlvError {
+  fn from(e: std::io::Error) -> Self {
+    match e.kind() {
+      std::io::ErrorKind::UnexpectedEof => ErrorKind::TruncatedInput.into(),
+      _ => ErrorKind::ReadError.into(),
+    }
+  }
+}
+
+// custom messages are truncated, so that errors stay cheap and usable without allocation
+#[cfg(feature = "serde")]
+impl serde::ser::Error for TlvError {
+  fn custom<T: fmt::Display>(msg: T) -> Self {
+    let mut e = Self::new(ErrorKind::Custom);
+    e.detail = Some(Detail::Message(Message::new(msg)));
+    e
+  }
+}
+
+#[cfg(feature = "serde")]
+impl serde::de::Error for TlvError {
+  fn custom<T: fmt::Display>(msg: T) -> Self {
+    <Self as serde::ser::Error>::custom(msg)
+  }
+
+  fn missing_field(field: &'static str) -> Self {
+    Self::new(ErrorKind::MissingField).with_detail(field)
   }
 }
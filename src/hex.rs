@@ -0,0 +1,95 @@
+//! Hex string representation of tags and values, used by serde impls
+//!
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Serialize, Serializer};
+
+use crate::TlvError;
+
+/// Displays and serializes bytes as an uppercase hex string, without separators.
+pub(crate) struct Hex<'a>(pub(crate) &'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for b in self.0 {
+            write!(f, "{b:02X}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Hex<'_> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
+
+/// Deserializes bytes from a hex string, for `#[serde(deserialize_with)]`.
+#[cfg(feature = "alloc")]
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+    d.deserialize_str(BytesVisitor)
+}
+
+/// Decodes a hex string, in upper or lower case.
+#[cfg(feature = "alloc")]
+pub(crate) fn decode(v: &str) -> Option<Vec<u8>> {
+    let chunks = v.as_bytes().chunks_exact(2);
+    if !chunks.remainder().is_empty() {
+        return None;
+    }
+    chunks
+        .map(|c| {
+            let hi = char::from(c[0]).to_digit(16)?;
+            let lo = char::from(c[1]).to_digit(16)?;
+            u8::try_from(hi << 4 | lo).ok()
+        })
+        .collect()
+}
+
+#[cfg(feature = "alloc")]
+pub(crate) struct BytesVisitor;
+
+#[cfg(feature = "alloc")]
+impl Visitor<'_> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a hex string")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        decode(v).ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+/// Deserializes a tag from its hex string representation.
+pub(crate) fn deserialize_tag<'de, T, D>(d: D) -> Result<T, D::Error>
+where
+    T: for<'a> TryFrom<&'a str, Error = TlvError>,
+    D: Deserializer<'de>,
+{
+    d.deserialize_str(TagVisitor(PhantomData))
+}
+
+struct TagVisitor<T>(PhantomData<T>);
+
+impl<T> Visitor<'_> for TagVisitor<T>
+where
+    T: for<'a> TryFrom<&'a str, Error = TlvError>,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a hex encoded tag")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        T::try_from(v).map_err(E::custom)
+    }
+}
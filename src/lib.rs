@@ -1,7 +1,27 @@
 //! This crate provides tools and utilities for handling TLV data as
 //! defined in [ISO7816-4][iso7816-4].
 //!
-//! This include BER-TLV data or SIMPLE-TLV data objects.
+//! This include BER-TLV data, SIMPLE-TLV data or COMPACT-TLV data objects.
+//!
+//! Answer-to-Reset (ATR) can also be decoded, see `atr`.
+//!
+//! # Features
+//! - `alloc` (default): owned data objects (`ber::Tlv`, `simple::Tlv`, ...), backed by `Vec`.
+//!   Without it, BER-TLV and SIMPLE-TLV data can still be parsed with [`ber::TlvRef`], [`ber::TlvIter`]
+//!   and [`simple::TlvIter`], and built into fixed-size buffers with [`ber::TlvRef::new()`]
+//!   and [`simple::TlvRef::new()`]. ATRs are decoded as well, their historical bytes being left raw.
+//! - `std`: implements `std::error::Error` for [`TlvError`], adds `IoSink` to use any `std::io::Write`
+//!   as a [`Sink`], and `read_from()`/`write_to()` to stream data objects through `std::io`.
+//! - `long-tags`: accepts BER-TLV tags of up to 6 bytes (tag numbers of up to 32 bits),
+//!   as found in generic ASN.1 data. Such tags are not [ISO7816-4][iso7816-4] compliant,
+//!   see [`ber::Tag::iso7816_compliant`].
+//! - `serde`: implements `Serialize` and `Deserialize` for BER-TLV and SIMPLE-TLV tags and data objects.
+//!   Tags and primitive values are represented as hex strings, constructed values as lists of data objects:
+//!   `{"tag":"6F","value":[{"tag":"84","value":"A000000003"}]}`.
+//!   With `alloc`, `ber::to_vec()` and `ber::from_bytes()` also map Rust data structures
+//!   to BER-TLV data, with tags given by field names.
+//! - `derive`: `#[derive(BerTlv)]` maps Rust structs to BER-TLV templates,
+//!   with tags given by `#[tlv(...)]` attributes, see `ber::BerTlv`.
 //!
 //!
 //!
@@ -9,8 +29,8 @@
 //! [iso7816-4]: https://www.iso.org/standard/54550.html
 
 #![deny(missing_docs)]
-#![cfg_attr(feature = "cargo-clippy", deny(clippy::all))]
-#![cfg_attr(feature = "cargo-clippy", deny(clippy::pedantic))]
+#![deny(clippy::all)]
+#![deny(clippy::pedantic)]
 // otherwise cargo doc fails with
 // error: no global memory allocator found but one is required; link to std or add #[global_allocator] to
 // a static item that implements the GlobalAlloc trait.
@@ -24,21 +44,37 @@ use static_alloc::Bump;
 static ALLOC: Bump<[u8; 1 << 28]> = Bump::uninit();
 
 // use vectors
+#[cfg(any(test, feature = "alloc"))]
 #[macro_use]
 extern crate alloc;
 
+#[cfg(feature = "std")]
+extern crate std;
+
 #[cfg(test)]
 #[macro_use]
 extern crate hex_literal;
 
+// `#[derive(BerTlv)]` refers to this crate by name
+#[cfg(all(test, feature = "derive"))]
+extern crate self as iso7816_tlv;
+
 use core::result;
 
 // internal organization
+pub mod atr;
 pub mod ber;
+pub mod compact;
 mod error;
+#[cfg(feature = "serde")]
+mod hex;
+mod sink;
 pub mod simple;
 
 // custom reexport (structs at same level for users)
-pub use error::TlvError;
+pub use error::{ErrorKind, TlvError};
+pub use sink::Sink;
+#[cfg(feature = "std")]
+pub use sink::IoSink;
 
 type Result<T> = result::Result<T, TlvError>;
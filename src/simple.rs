@@ -6,12 +6,15 @@
 //!
 //! [iso7816-4]: https://www.iso.org/standard/54550.html
 //!
+#[cfg(feature = "alloc")]
 use alloc::vec::Vec;
 use core::convert::TryFrom;
 
 use untrusted::{Input, Reader};
 
-use crate::{Result, TlvError};
+#[cfg(feature = "std")]
+use crate::IoSink;
+use crate::{ErrorKind, Result, Sink, TlvError};
 
 /// Tag for SIMPLE-TLV data as defined in [ISO7816-4].
 /// > The tag field consists of a single byte encoding a tag number from 1 to 254.
@@ -27,7 +30,7 @@ use crate::{Result, TlvError};
 /// ```rust
 /// use std::convert::TryFrom;
 /// use iso7816_tlv::simple::Tag;
-/// # use iso7816_tlv::TlvError;
+/// # use iso7816_tlv::{ErrorKind, TlvError};
 /// # fn main() -> Result<(), TlvError> {
 ///
 /// // get tag from u8 or &str
@@ -67,11 +70,11 @@ impl Tag {
     /// ```
     ///
     /// # Errors
-    /// This method returns `Err(TlvError::InvalidInput)` if `v` is not a legal
+    /// This method returns `Err(ErrorKind::InvalidInput.into())` if `v` is not a legal
     /// tag (e.g., if v is `0x00` or `0xFF`).
     pub const fn try_from_u8(v: u8) -> Result<Self> {
         match v {
-            0x00 | 0xFF => Err(TlvError::InvalidInput),
+            0x00 | 0xFF => Err(TlvError::new(ErrorKind::InvalidInput)),
             _ => Ok(Self(v)),
         }
     }
@@ -83,17 +86,48 @@ impl Tag {
 /// > In this case there is no value field.
 ///
 /// In this case Value is an empty vector
+#[cfg(feature = "alloc")]
 pub type Value = Vec<u8>;
 
 /// SIMPLE-TLV data object representation.
 /// > Each SIMPLE-TLV data object shall consist of two or three consecutive fields:
 /// > a mandatory tag field, a mandatory length field and a conditional value field
+#[cfg(feature = "alloc")]
 #[derive(PartialEq, Debug, Clone)]
 pub struct Tlv {
     tag: Tag,
     value: Value,
 }
 
+/// Borrowed SIMPLE-TLV data object, whose value is a slice of the parsed input.
+///
+/// Contrary to [`Tlv`], parsing and encoding into fixed-size buffers need no allocation.
+///
+/// # Example
+/// ```rust
+/// use std::convert::TryFrom;
+/// use iso7816_tlv::simple::{Tag, TlvRef};
+/// # use iso7816_tlv::TlvError;
+///
+/// # fn main() -> Result<(), TlvError> {
+/// let data = [0x84, 0x01, 0x2C, 0x9E, 0x01, 0x42];
+/// let (tlv, rest) = TlvRef::parse(&data);
+/// assert_eq!(&[0x2C], tlv?.value());
+/// assert_eq!(&data[3..], rest);
+///
+/// let mut buf = [0; 8];
+/// let tlv = TlvRef::new(Tag::try_from(0x9E)?, &[0x42], &mut buf)?;
+/// assert_eq!(rest, tlv.as_bytes());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct TlvRef<'a> {
+    tag: Tag,
+    value: &'a [u8],
+    raw: &'a [u8],
+}
+
 // From impl may fail, not the converse
 #[allow(clippy::from_over_into)]
 impl Into<u8> for Tag {
@@ -117,15 +151,60 @@ impl TryFrom<&str> for Tag {
     }
 }
 
+/// Serialized as its hex encoding, such as `"80"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Tag {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> core::result::Result<S::Ok, S::Error> {
+        crate::hex::Hex(&[self.0]).serialize(s)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Tag {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> core::result::Result<Self, D::Error> {
+        crate::hex::deserialize_tag(d)
+    }
+}
+
+/// Serialized as a struct with `tag` and `value` fields, the value as a hex string:
+/// `{"tag":"80","value":"0102"}`.
+#[cfg(all(feature = "serde", feature = "alloc"))]
+impl serde::Serialize for Tlv {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> core::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut tlv = s.serialize_struct("Tlv", 2)?;
+        tlv.serialize_field("tag", &self.tag)?;
+        tlv.serialize_field("value", &crate::hex::Hex(&self.value))?;
+        tlv.end()
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "alloc"))]
+impl<'de> serde::Deserialize<'de> for Tlv {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> core::result::Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Tlv")]
+        struct Repr {
+            tag: Tag,
+            #[serde(deserialize_with = "crate::hex::deserialize")]
+            value: Value,
+        }
+
+        let Repr { tag, value } = Repr::deserialize(d)?;
+        Self::new(tag, value).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "alloc")]
 impl Tlv {
     /// Create a SIMPLE-TLV data object from valid tag and value.
     /// A value has a maximum size of `65_535` bytes.
     ///
     /// # Errors
-    /// Fails with `TlvError::InvalidLength` if value is longer than `65_535` bytes.
+    /// Fails with `ErrorKind::InvalidLength` if value is longer than `65_535` bytes.
     pub fn new(tag: Tag, value: Value) -> Result<Self> {
         if value.len() > 65_536 {
-            Err(TlvError::InvalidLength)
+            Err(ErrorKind::InvalidLength.into())
         } else {
             Ok(Self { tag, value })
         }
@@ -164,33 +243,49 @@ impl Tlv {
         ret
     }
 
-    fn read_len(r: &mut Reader) -> Result<usize> {
-        let mut ret: usize = 0;
-        let x = r.read_byte()?;
-        if x == 0xFF {
-            for _ in 0..2 {
-                let x = r.read_byte()?;
-                ret = ret << 8 | usize::from(x);
-            }
-        } else {
-            ret = usize::from(x);
-        }
-        Ok(ret)
+    /// serializes self into a byte [`Sink`], without copying the value.
+    /// # Errors
+    /// Fails with [`ErrorKind::InvalidLength`] if value is longer than `65_535` bytes,
+    /// or if the sink fails, see [`Sink::write_all()`].
+    pub fn encode_to<S: Sink + ?Sized>(&self, sink: &mut S) -> Result<()> {
+        let mut header = [0; 4];
+        sink.write_all(encode_header(self.tag, self.value.len(), &mut header)?)?;
+        sink.write_all(&self.value)
     }
 
-    fn read(r: &mut Reader) -> Result<Self> {
-        let tag = Tag::try_from(r.read_byte()?)?;
-        let len = Self::read_len(r)?;
-        let content = r.read_bytes(len)?;
+    /// serializes self into an [`std::io::Write`].
+    /// # Errors
+    /// Fails with [`ErrorKind::WriteError`] if the writer fails.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write + ?Sized>(&self, w: &mut W) -> Result<()> {
+        self.encode_to(&mut IoSink::new(w))
+    }
 
-        Ok(Self {
-            tag,
-            value: content.as_slice_less_safe().to_vec(),
-        })
+    /// Reads a SIMPLE-TLV data object from an [`std::io::Read`].
+    /// Bytes following the data object are not consumed.
+    /// # Errors
+    /// Fails with [`ErrorKind::TruncatedInput`] if the end of the reader is reached
+    /// before the end of the data object, with [`ErrorKind::ReadError`] if the reader fails,
+    /// or if the tag is not valid.
+    #[cfg(feature = "std")]
+    pub fn read_from<R: std::io::Read + ?Sized>(r: &mut R) -> Result<Self> {
+        let mut header = [0_u8; 2];
+        r.read_exact(&mut header)?;
+        let tag = Tag::try_from(header[0])?;
+        let len = if header[1] == 0xFF {
+            let mut len = [0_u8; 2];
+            r.read_exact(&mut len)?;
+            usize::from(u16::from_be_bytes(len))
+        } else {
+            usize::from(header[1])
+        };
+        let mut value = vec![0; len];
+        r.read_exact(&mut value)?;
+        Ok(Self { tag, value })
     }
 
     /// Parses a byte array into a SIMPLE-TLV structure.
-    /// This also returns the unprocessed data.
+    /// This also returns the unprocessed data, that is the whole input on error.
     /// # Example (parse mulitple tlv in input)
     /// ```rust
     /// use iso7816_tlv::simple::Tlv;
@@ -213,39 +308,264 @@ impl Tlv {
     /// }
     /// ```
     pub fn parse(input: &[u8]) -> (Result<Self>, &[u8]) {
-        let mut r = Reader::new(Input::from(input));
-        (
-            Self::read(&mut r),
-            r.read_bytes_to_end().as_slice_less_safe(),
-        )
+        let (r, rest) = TlvRef::parse(input);
+        (r.map(|t| Self::from(&t)), rest)
     }
 
     /// Parses a byte array into a vector of SIMPLE-TLV.
     /// # Note
     /// Errors are discarded and parsing stops at first error
-    /// Prefer using the [`parse()`](Self::parse()) method and iterate over returned processed data.
+    /// Prefer using [`TlvIter`] to get errors and resume parsing.
     #[must_use]
     pub fn parse_all(input: &[u8]) -> Vec<Self> {
-        let mut ret = Vec::new();
-        let mut r = Reader::new(Input::from(input));
-        while !r.at_end() {
-            if Self::read(&mut r).map(|elem| ret.push(elem)).is_err() {
-                break;
-            }
-        }
-        ret
+        TlvIter::new(input)
+            .map_while(Result::ok)
+            .map(|t| Self::from(&t))
+            .collect()
     }
 
     /// Parses a byte array into a SIMPLE-TLV structure.
     /// Input must exactly match a SIMPLE-TLV object.
     /// # Errors
-    /// Fails with `TlvError::InvalidInput` if input does not match a SIMPLE-TLV object.
+    /// Fails with `ErrorKind::InvalidInput` if input does not match a SIMPLE-TLV object.
     pub fn from_bytes(input: &[u8]) -> Result<Self> {
         let (r, n) = Self::parse(input);
         if n.is_empty() {
             r
         } else {
-            Err(TlvError::InvalidInput)
+            Err(ErrorKind::InvalidInput.into())
+        }
+    }
+}
+
+impl<'a> TlvRef<'a> {
+    /// Creates a SIMPLE-TLV data object by encoding tag and value at the beginning of `buf`,
+    /// without allocating.
+    ///
+    /// # Errors
+    /// Fails with [`ErrorKind::InvalidLength`] if value is longer than `65_535` bytes,
+    /// or with [`ErrorKind::BufferTooSmall`] if `buf` cannot hold the encoded data object.
+    pub fn new(tag: Tag, value: &[u8], buf: &'a mut [u8]) -> Result<Self> {
+        let mut header_buf = [0; 4];
+        let header = encode_header(tag, value.len(), &mut header_buf)?;
+        let len = header.len() + value.len();
+        let out = buf.get_mut(..len).ok_or(ErrorKind::BufferTooSmall)?;
+        let mut sink = &mut *out;
+        sink.write_all(header)?;
+        sink.write_all(value)?;
+        Self::from_bytes(out)
+    }
+
+    /// Get SIMPLE-TLV tag.
+    #[must_use]
+    pub fn tag(&self) -> Tag {
+        self.tag
+    }
+
+    /// Get SIMPLE-TLV value length
+    #[must_use]
+    pub fn length(&self) -> usize {
+        self.value.len()
+    }
+
+    /// Get SIMPLE-TLV value as a slice of the parsed input.
+    #[must_use]
+    pub fn value(&self) -> &'a [u8] {
+        self.value
+    }
+
+    /// Get the whole SIMPLE-TLV encoding (tag, length and value) as a slice of the parsed input.
+    #[must_use]
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// Converts self into an owned [`Tlv`].
+    #[cfg(feature = "alloc")]
+    #[must_use]
+    pub fn to_tlv(&self) -> Tlv {
+        Tlv::from(self)
+    }
+
+    /// Writes the encoding of self into a byte [`Sink`].
+    /// # Errors
+    /// Fails if the sink fails, see [`Sink::write_all()`].
+    pub fn encode_to<S: Sink + ?Sized>(&self, sink: &mut S) -> Result<()> {
+        sink.write_all(self.raw)
+    }
+
+    fn read(r: &mut Reader<'a>) -> Result<Self> {
+        let (raw, (tag, value)) = r.read_partial(|r| -> Result<_> {
+            let tag = Tag::try_from(r.read_byte()?)?;
+            let len = read_len(r)?;
+            Ok((tag, r.read_bytes(len)?))
+        })?;
+        Ok(Self {
+            tag,
+            value: value.as_slice_less_safe(),
+            raw: raw.as_slice_less_safe(),
+        })
+    }
+
+    /// Parses a byte array into a borrowed SIMPLE-TLV structure.
+    /// This also returns the unprocessed data, that is the whole input on error.
+    pub fn parse(input: &'a [u8]) -> (Result<Self>, &'a [u8]) {
+        let mut r = Reader::new(Input::from(input));
+        match Self::read(&mut r) {
+            Ok(tlv) => (Ok(tlv), r.read_bytes_to_end().as_slice_less_safe()),
+            Err(e) => (Err(e), input),
+        }
+    }
+
+    /// Parses a byte array into a borrowed SIMPLE-TLV structure.
+    /// Input must exactly match a SIMPLE-TLV object.
+    /// # Errors
+    /// Fails with `ErrorKind::InvalidInput` if input does not match a SIMPLE-TLV object.
+    pub fn from_bytes(input: &'a [u8]) -> Result<Self> {
+        let (r, n) = Self::parse(input);
+        if n.is_empty() {
+            r
+        } else {
+            Err(ErrorKind::InvalidInput.into())
+        }
+    }
+}
+
+impl PartialEq for TlvRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.tag == other.tag && self.value == other.value
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<&TlvRef<'_>> for Tlv {
+    fn from(t: &TlvRef<'_>) -> Self {
+        Self {
+            tag: t.tag,
+            value: t.value.to_vec(),
+        }
+    }
+}
+
+fn read_len(r: &mut Reader) -> Result<usize> {
+    let mut ret: usize = 0;
+    let x = r.read_byte()?;
+    if x == 0xFF {
+        for _ in 0..2 {
+            let x = r.read_byte()?;
+            ret = ret << 8 | usize::from(x);
+        }
+    } else {
+        ret = usize::from(x);
+    }
+    Ok(ret)
+}
+
+// encodes the tag and length fields of a data object whose value is `len` bytes long
+#[allow(clippy::cast_possible_truncation)]
+fn encode_header(tag: Tag, len: usize, buf: &mut [u8; 4]) -> Result<&[u8]> {
+    buf[0] = tag.0;
+    match u16::try_from(len) {
+        Ok(len @ 0..=254) => {
+            buf[1] = len as u8;
+            Ok(&buf[..2])
+        }
+        Ok(len) => {
+            buf[1] = 0xFF;
+            buf[2..].copy_from_slice(&len.to_be_bytes());
+            Ok(&buf[..])
+        }
+        Err(_) => Err(ErrorKind::InvalidLength.into()),
+    }
+}
+
+/// Lazy iterator over consecutive SIMPLE-TLV data objects of a byte array.
+///
+/// Items are borrowed [`TlvRef`], so that no allocation is needed
+/// (see [`TlvRef::to_tlv()`] for owned data objects). Each item is parsed on demand. When an object fails to parse, the error is
+/// yielded and iteration stops; [`offset()`](Self::offset()) then gives the position
+/// of the faulty object in the input, and iteration may go on using
+/// [`resume_at()`](Self::resume_at()).
+///
+/// # Example
+/// ```rust
+/// use iso7816_tlv::simple::TlvIter;
+/// # use iso7816_tlv::{ErrorKind, TlvError};
+///
+/// # fn main() -> Result<(), TlvError> {
+/// let data = [0x84, 0x01, 0x2C, 0x00, 0x01, 0x00, 0x9E, 0x01, 0x42];
+/// let mut iter = TlvIter::new(&data);
+/// assert_eq!(&[0x2C], iter.next().ok_or(ErrorKind::InvalidInput)??.value());
+///
+/// // 00 is not a valid tag
+/// let err = iter.next().and_then(Result::err).ok_or(ErrorKind::InvalidInput)?;
+/// assert_eq!(ErrorKind::InvalidInput, err.kind());
+/// assert_eq!(Some(3), err.offset());
+/// assert_eq!(3, iter.offset());
+///
+/// iter.resume_at(6);
+/// assert_eq!(&[0x42], iter.next().ok_or(ErrorKind::InvalidInput)??.value());
+/// assert_eq!(None, iter.next());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TlvIter<'a> {
+    input: &'a [u8],
+    offset: usize,
+    stopped: bool,
+}
+
+impl<'a> TlvIter<'a> {
+    /// Creates an iterator over the SIMPLE-TLV data objects in `input`.
+    #[must_use]
+    pub fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            offset: 0,
+            stopped: false,
+        }
+    }
+
+    /// Offset in the input of the next data object to parse.
+    /// After an error, this is the offset of the data object that failed to parse.
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Unprocessed part of the input.
+    #[must_use]
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.input[self.offset..]
+    }
+
+    /// Restarts iteration at the given offset of the input,
+    /// for instance to skip over a faulty data object.
+    /// Offsets past the end of input are clamped to the input length.
+    pub fn resume_at(&mut self, offset: usize) {
+        self.offset = offset.min(self.input.len());
+        self.stopped = false;
+    }
+}
+
+impl<'a> Iterator for TlvIter<'a> {
+    type Item = Result<TlvRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stopped || self.offset >= self.input.len() {
+            return None;
+        }
+        let remaining = self.remaining();
+        match TlvRef::parse(remaining) {
+            (Ok(tlv), left) => {
+                self.offset += remaining.len() - left.len();
+                Some(Ok(tlv))
+            }
+            (Err(e), _) => {
+                self.stopped = true;
+                Some(Err(e.located(self.offset, None, 0)))
+            }
         }
     }
 }
@@ -254,6 +574,7 @@ impl Tlv {
 mod tests {
     use super::*;
     use core::convert::TryFrom;
+    #[cfg(feature = "alloc")]
     use rand_core::{RngCore, SeedableRng};
 
     #[test]
@@ -274,6 +595,7 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "alloc")]
     #[test]
     fn parse_1() -> Result<()> {
         let in_data = [
@@ -318,6 +640,50 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn borrowed() -> Result<()> {
+        let tag = Tag::try_from(0x84)?;
+        let mut buf = [0; 260];
+        let tlv = TlvRef::new(tag, &[0x2C], &mut buf)?;
+        assert_eq!(&[0x84, 0x01, 0x2C], tlv.as_bytes());
+        // length fields on three bytes are equivalent
+        let long = TlvRef::from_bytes(&hex!("84 FF 0001 2C"))?;
+        assert_eq!(tlv, long);
+        assert_eq!(
+            (tag, 1, &[0x2C][..]),
+            (long.tag(), long.length(), long.value())
+        );
+
+        let mut out = [0; 4];
+        let mut sink = &mut out[..];
+        tlv.encode_to(&mut sink)?;
+        assert_eq!(1, sink.len());
+        assert_eq!(
+            Err(ErrorKind::WriteError),
+            long.encode_to(&mut &mut out[..]).map_err(|e| e.kind())
+        );
+
+        let value = [0xAA; 255];
+        let tlv = TlvRef::new(tag, &value, &mut buf)?;
+        assert_eq!(&[0x84, 0xFF, 0x00, 0xFF], &tlv.as_bytes()[..4]);
+        assert_eq!(&value[..], tlv.value());
+        assert_eq!(
+            Err(ErrorKind::BufferTooSmall),
+            TlvRef::new(tag, &value, &mut buf[..258]).map_err(|e| e.kind())
+        );
+        assert_eq!(
+            Err(ErrorKind::InvalidLength),
+            TlvRef::new(tag, &vec![0; 65_536], &mut buf).map_err(|e| e.kind())
+        );
+
+        let input = hex!("84 02 2C");
+        let (r, rest) = TlvRef::parse(&input);
+        assert_eq!(Err(ErrorKind::TruncatedInput), r.map_err(|e| e.kind()));
+        assert_eq!(&input, rest);
+        Ok(())
+    }
+
+    #[cfg(feature = "alloc")]
     #[test]
     fn parse_multiple() {
         let in_data = hex!(
@@ -341,6 +707,28 @@ mod tests {
         assert_eq!(parsed_manual, parsed_at_once);
     }
 
+    #[test]
+    fn iter_error_and_resume() -> Result<()> {
+        // length of the second object exceeds input
+        let in_data = hex!("03 01 01 04 05 04");
+        let mut iter = TlvIter::new(&in_data);
+        assert_eq!(
+            TlvRef::from_bytes(&in_data[..3]),
+            iter.next().ok_or(ErrorKind::InvalidInput)?
+        );
+        assert_eq!(
+            Some(Err(ErrorKind::TruncatedInput)),
+            iter.next().map(|r| r.map_err(|e| e.kind()))
+        );
+        assert_eq!(3, iter.offset());
+        assert_eq!(None, iter.next());
+
+        iter.resume_at(in_data.len());
+        assert_eq!(None, iter.next());
+        Ok(())
+    }
+
+    #[cfg(feature = "alloc")]
     #[test]
     #[allow(clippy::cast_possible_truncation)]
     fn serialize_parse() -> Result<()> {
@@ -352,10 +740,64 @@ mod tests {
             let ser = tlv.to_vec();
             let tlv_2 = Tlv::from_bytes(&ser)?;
             assert_eq!(tlv, tlv_2);
+            let mut sink = Vec::new();
+            tlv.encode_to(&mut sink)?;
+            assert_eq!(ser, sink);
 
             assert_eq!(r, tlv.tag().into());
             assert_eq!(v, tlv.value());
         }
         Ok(())
     }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn read_write_io() -> Result<()> {
+        let mut in_data = hex!("03 01 01 04 FF 01 00").to_vec();
+        in_data.extend_from_slice(&[0xAA; 256]);
+        in_data.extend_from_slice(&hex!("07 02 01"));
+        let mut reader = &in_data[..];
+        let tlv = Tlv::read_from(&mut reader)?;
+        assert_eq!(Tlv::new(Tag::try_from(3)?, vec![1])?, tlv);
+        let long = Tlv::read_from(&mut reader)?;
+        assert_eq!(&[0xAA; 256], long.value());
+        assert_eq!(
+            Err(ErrorKind::TruncatedInput),
+            Tlv::read_from(&mut reader).map_err(|e| e.kind())
+        );
+        assert_eq!(
+            Err(ErrorKind::InvalidInput),
+            Tlv::read_from(&mut &hex!("00 00")[..]).map_err(|e| e.kind())
+        );
+
+        let mut out = Vec::new();
+        tlv.write_to(&mut out)?;
+        long.write_to(&mut out)?;
+        assert_eq!(&in_data[..out.len()], &out[..]);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(all(feature = "serde", feature = "alloc"))]
+    fn serde() -> Result<()> {
+        use serde_test::{assert_de_tokens_error, assert_tokens, Token};
+
+        let tlv = Tlv::new(Tag::try_from(0x84)?, vec![0x01, 0xAB])?;
+        assert_tokens(
+            &tlv,
+            &[
+                Token::Struct {
+                    name: "Tlv",
+                    len: 2,
+                },
+                Token::Str("tag"),
+                Token::Str("84"),
+                Token::Str("value"),
+                Token::Str("01AB"),
+                Token::StructEnd,
+            ],
+        );
+        assert_de_tokens_error::<Tag>(&[Token::Str("FF")], "Invalid tag encountered");
+        Ok(())
+    }
 }
@@ -0,0 +1,86 @@
+//! Output of encoded TLV data
+//!
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::{ErrorKind, Result};
+
+/// Byte sink encoded TLV data can be written to.
+///
+/// This is the byte-oriented counterpart of [`core::fmt::Write`].
+/// It is implemented by `Vec<u8>` (with the `alloc` feature)
+/// and by `&mut [u8]`, which is advanced past written bytes.
+/// With the `std` feature, any [`std::io::Write`] can be used through `IoSink`.
+pub trait Sink {
+    /// Writes all given bytes.
+    /// # Errors
+    /// Fails with [`ErrorKind::WriteError`] if bytes could not be written.
+    fn write_all(&mut self, bytes: &[u8]) -> Result<()>;
+}
+
+#[cfg(feature = "alloc")]
+impl Sink for Vec<u8> {
+    fn write_all(&mut self, bytes: &[u8]) -> Result<()> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl Sink for &mut [u8] {
+    fn write_all(&mut self, bytes: &[u8]) -> Result<()> {
+        if bytes.len() > self.len() {
+            return Err(ErrorKind::WriteError.into());
+        }
+        let (out, rest) = core::mem::take(self).split_at_mut(bytes.len());
+        out.copy_from_slice(bytes);
+        *self = rest;
+        Ok(())
+    }
+}
+
+/// [`Sink`] writing to a [`std::io::Write`].
+///
+/// # Example
+/// ```rust
+/// use iso7816_tlv::ber::{Tag, Tlv, Value};
+/// use iso7816_tlv::IoSink;
+/// # use std::convert::TryFrom;
+///
+/// # fn main() -> Result<(), iso7816_tlv::TlvError> {
+/// let tlv = Tlv::new(Tag::try_from(0x80_u32)?, Value::Primitive(vec![0xAA]))?;
+/// let mut sink = IoSink::new(std::io::Cursor::new(Vec::new()));
+/// tlv.encode_to(&mut sink)?;
+/// assert_eq!(vec![0x80, 0x01, 0xAA], sink.into_inner().into_inner());
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct IoSink<W> {
+    inner: W,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> IoSink<W> {
+    /// Creates a sink writing to `inner`.
+    #[must_use]
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Gets the underlying writer.
+    #[must_use]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Sink for IoSink<W> {
+    fn write_all(&mut self, bytes: &[u8]) -> Result<()> {
+        self.inner
+            .write_all(bytes)
+            .map_err(|_| ErrorKind::WriteError.into())
+    }
+}